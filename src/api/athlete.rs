@@ -0,0 +1,41 @@
+use serde::Deserialize;
+
+/// An athlete with the events of their whole career
+#[derive(Clone, Deserialize, PartialEq)]
+pub struct AthleteProfile {
+    pub id: u64,
+    pub firstname: String,
+    pub lastname: String,
+    #[serde(default)]
+    pub country: String,
+    #[serde(default)]
+    pub all_results: Vec<AthleteResult>,
+}
+
+/// The result of an athlete in a category of an event
+#[derive(Clone, Deserialize, PartialEq)]
+pub struct AthleteResult {
+    pub event_id: u64,
+    // season: String,
+    // rank: Option<u64>,
+    /// Like `boulder`
+    #[serde(default)]
+    pub discipline: String,
+    // event_name: String,
+    // date: NaiveDate,
+    /// Like `Women`
+    #[serde(default)]
+    pub category_name: String,
+}
+
+impl AthleteResult {
+    /// Whether this is the result in a category of an event, like
+    /// `BOULDER Women`. Results without a discipline or category could be in
+    /// any category.
+    pub fn is_in(&self, dcat_name: &str) -> bool {
+        self.discipline.is_empty()
+            || self.category_name.is_empty()
+            || dcat_name
+                .eq_ignore_ascii_case(&format!("{} {}", self.discipline, self.category_name))
+    }
+}
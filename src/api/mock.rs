@@ -0,0 +1,75 @@
+//! A mock of the IFSC API that serves the fixtures in `test_data`
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+/// The files served for every path of the API, relative to `test_data`
+const FIXTURES: &[(&str, &str)] = &[
+    ("", "api/seasons.json"),
+    ("seasons/35", "api/season_35.json"),
+    // This one contains PHP warnings, like the real API sometimes does
+    ("events/1297", "api/event_1297.json"),
+    // A paraclimbing event, which doesn't count for the World Cup ranking
+    ("events/1337", "api/event_1337.json"),
+    ("category_rounds/7685/results", "boulder_live_men_qualifications.json"),
+    ("category_rounds/7686/results", "boulder_live.json"),
+    ("category_rounds/8251/results", "boulder_live_3.json"),
+    ("category_rounds/8210/results", "results_boulder_finished.json"),
+    ("category_rounds/7977/results", "results_lead_live.json"),
+    ("athletes/1803", "api/athlete_1803.json"),
+    ("athletes/1811", "api/athlete_1811.json"),
+];
+
+/// Start the mock server, if it is not running yet, and point
+/// [`super::request`] to it.
+pub fn start() {
+    static URL: OnceLock<String> = OnceLock::new();
+    let url = URL.get_or_init(|| {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                handle(stream);
+            }
+        });
+        url
+    });
+    std::env::set_var(super::BASE_URL_VAR, url);
+}
+
+fn handle(mut stream: TcpStream) {
+    let mut reader = BufReader::new(&stream);
+    let mut request_line = String::new();
+    if reader.read_line(&mut request_line).is_err() {
+        return;
+    }
+
+    // We don't care about the headers, but we have to read them before
+    // responding.
+    let mut line = String::new();
+    while reader.read_line(&mut line).is_ok() && !line.trim_end().is_empty() {
+        line.clear();
+    }
+
+    let path = request_line.split_whitespace().nth(1).unwrap_or("/");
+    let (status, body) = match fixture(path) {
+        Some(body) => ("200 OK", body),
+        None => ("404 Not Found", String::new()),
+    };
+
+    let _ = write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+}
+
+fn fixture(path: &str) -> Option<String> {
+    let path = path.trim_matches('/');
+    let (_, file) = FIXTURES.iter().find(|(p, _)| *p == path)?;
+    let mut full_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    full_path.push("test_data");
+    full_path.push(file);
+    std::fs::read_to_string(full_path).ok()
+}
@@ -1,4 +1,7 @@
 use serde::de::DeserializeOwned;
+pub mod athlete;
+#[cfg(test)]
+pub mod mock;
 pub mod result;
 pub mod seasons;
 
@@ -8,12 +11,20 @@ const BASE_URL: &str = if cfg!(feature = "desktop") {
     "https://ifsc-proxy.donsz.nl/"
 };
 
+/// Environment variable to use another server than the IFSC API, such as the
+/// mock server in the tests
+const BASE_URL_VAR: &str = "BOULDERBOARD_API_URL";
+
+fn base_url() -> String {
+    std::env::var(BASE_URL_VAR).unwrap_or_else(|_| BASE_URL.to_string())
+}
+
 pub async fn request<T: DeserializeOwned>(url: String) -> Option<T> {
-    let full_url = dbg!(format!("{BASE_URL}{url}"));
+    let full_url = format!("{}{url}", base_url());
     let res = reqwest::get(&full_url).await.ok()?.text().await.ok()?;
     let cleaned = clean_api_output(res);
 
-    #[cfg(feature = "desktop")]
+    #[cfg(all(feature = "desktop", not(test)))]
     {
         use std::{
             io::Write,
@@ -33,7 +44,13 @@ pub async fn request<T: DeserializeOwned>(url: String) -> Option<T> {
         write!(f, "{}", &cleaned).unwrap();
     }
 
-    serde_json::from_str(&cleaned).map_err(|e| dbg!(e)).ok()
+    match serde_json::from_str(&cleaned) {
+        Ok(value) => Some(value),
+        Err(e) => {
+            eprintln!("Could not read {full_url}: {e}");
+            None
+        }
+    }
 }
 
 /// Remove random fucking PHP warnings from the output
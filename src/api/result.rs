@@ -1,12 +1,17 @@
+use std::collections::HashMap;
 use std::fmt::Display;
 
-use serde::Deserialize;
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize};
 
-#[derive(Deserialize, Debug)]
+#[derive(Clone, Deserialize, Debug, PartialEq, Eq, Hash, clap::ValueEnum)]
 pub enum DisciplineTag {
     Lead,
     Boulder,
     Speed,
+    /// A discipline we don't know yet, like a combined format
+    #[serde(other)]
+    #[value(skip)]
+    Unknown,
 }
 
 impl Display for DisciplineTag {
@@ -14,21 +19,27 @@ impl Display for DisciplineTag {
         match self {
             Self::Lead => f.write_str("Lead"),
             Self::Boulder => f.write_str("Boulder"),
-            Self::Speed => f.write_str("Speed")
+            Self::Speed => f.write_str("Speed"),
+            Self::Unknown => f.write_str("Unknown discipline"),
         }
     }
 }
 
-#[derive(Clone, Deserialize, Debug, PartialEq, Eq)]
+#[derive(Clone, Default, Serialize, Deserialize, Debug, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
 pub enum Status {
     Active,
     Pending,
     Locked,
     Confirmed,
+    /// A status we don't know yet, so that it doesn't make the whole results
+    /// unreadable
+    #[default]
+    #[serde(other)]
+    Unknown,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Clone, Deserialize, Debug, PartialEq)]
 pub struct Results {
     // id: u64,
     pub discipline: DisciplineTag,
@@ -53,7 +64,7 @@ pub struct Results {
 //     startlist: String,
 // }
 
-#[derive(Deserialize, Debug)]
+#[derive(Clone, Deserialize, Debug, PartialEq)]
 pub struct Athlete {
     pub athlete_id: u64,
     // name: Option<String>,
@@ -64,7 +75,7 @@ pub struct Athlete {
     pub country: Country,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Clone, Deserialize, Debug, PartialEq)]
 pub struct Country {
     pub country: String,
     pub flag_url: String,
@@ -78,52 +89,131 @@ pub struct Country {
 //     route_start_positions: Vec<RouteStartPosition>,
 // }
 
-// #[derive(Deserialize, Debug)]
-// pub struct RouteStartPosition {
-//     route_name: String,
-//     route_id: u64,
-//     position: u64,
-// }
+/// The position of an athlete in the start order of a route
+#[derive(Clone, Debug, PartialEq)]
+pub struct RouteStartOrder {
+    pub route_name: String,
+    pub position: u64,
+}
 
-#[derive(Deserialize, Debug)]
+#[derive(Clone, Deserialize, Debug, PartialEq)]
 pub struct RankAthlete {
     #[serde(flatten)]
     pub athlete: Athlete,
-    // rank: u64,
-    // score: String,
+    /// The official rank, missing for athletes that did not start
+    pub rank: Option<u64>,
+    /// The official score, in the notation of the discipline
+    pub score: Option<String>,
     // start_order: Option<u64>,
+    /// Qualifications might be split into groups that are ranked separately
+    pub starting_group: Option<String>,
+    pub group_rank: Option<u64>,
+    /// Like `Visually Impaired Climbing: B1`, only for paraclimbing
+    #[serde(default)]
+    pub paraclimbing_sport_class: Option<String>,
+    /// Whether the sport class is final, like `confirmed` or
+    /// `fixed_review_date`
+    #[serde(default)]
+    pub sport_class_state: Option<String>,
+    /// Rounds on several routes have a start order for every route, in fields
+    /// like `route_D_start_order`
+    #[serde(flatten, deserialize_with = "route_start_orders")]
+    pub route_start_orders: Vec<RouteStartOrder>,
     pub ascents: Vec<Ascent>,
+    #[serde(default, deserialize_with = "lenient_or_default")]
     pub active: bool,
 }
 
-#[derive(Clone, Deserialize, Debug)]
+impl RankAthlete {
+    /// The short name of the sport class, like `B1`
+    pub fn sport_class(&self) -> Option<&str> {
+        let class = self.paraclimbing_sport_class.as_deref()?;
+        Some(class.rsplit(": ").next().unwrap_or(class))
+    }
+}
+
+/// Collect the start orders from the fields of an athlete, ordered by route
+fn route_start_orders<'de, D>(deserializer: D) -> Result<Vec<RouteStartOrder>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let fields = HashMap::<String, serde_json::Value>::deserialize(deserializer)?;
+    let mut orders: Vec<_> = fields
+        .into_iter()
+        .filter_map(|(key, value)| {
+            let route_name = key.strip_prefix("route_")?.strip_suffix("_start_order")?;
+            Some(RouteStartOrder {
+                route_name: route_name.to_string(),
+                position: value.as_u64()?,
+            })
+        })
+        .collect();
+    orders.sort_by(|a, b| a.route_name.cmp(&b.route_name));
+    Ok(orders)
+}
+
+/// Deserialize a field that might be missing or have an unexpected type as
+/// `None`, so that one odd ascent doesn't make the whole results unreadable.
+fn lenient<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: DeserializeOwned,
+{
+    let value = serde_json::Value::deserialize(deserializer)?;
+    Ok(T::deserialize(value).ok())
+}
+
+/// Like [`lenient`], for fields where a default is as good as a missing value
+fn lenient_or_default<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: DeserializeOwned + Default,
+{
+    Ok(lenient(deserializer)?.unwrap_or_default())
+}
+
+/// An ascent on a route. The fields of every discipline are optional, it is
+/// up to the discipline to decide which of them it needs.
+#[derive(Clone, Deserialize, Debug, PartialEq)]
 pub struct Ascent {
     // route_id: u64,
     // route_name: String,
     // modified: Option<DateTime<FixedOffset>>,
+    #[serde(default, deserialize_with = "lenient_or_default")]
     pub status: Status,
     #[serde(flatten)]
-    pub boulder: Option<BoulderAscent>,
+    pub boulder: BoulderAscent,
     #[serde(flatten)]
-    pub lead: Option<LeadAscent>,
+    pub lead: LeadAscent,
     #[serde(flatten)]
-    pub speed: Option<SpeedAscent>,
+    pub speed: SpeedAscent,
 }
 
-#[derive(Clone, Deserialize, Debug)]
+#[derive(Clone, Default, Deserialize, Debug, PartialEq)]
 pub struct BoulderAscent {
-    pub top: bool,
+    #[serde(default, deserialize_with = "lenient")]
+    pub top: Option<bool>,
+    #[serde(default, deserialize_with = "lenient")]
     pub top_tries: Option<u64>,
-    pub zone: bool,
+    #[serde(default, deserialize_with = "lenient")]
+    pub zone: Option<bool>,
+    #[serde(default, deserialize_with = "lenient")]
     pub zone_tries: Option<u64>,
 }
 
-#[derive(Clone, Deserialize, Debug)]
+#[derive(Clone, Default, Deserialize, Debug, PartialEq)]
 pub struct LeadAscent {
-    pub score: String,
+    #[serde(default, deserialize_with = "lenient")]
+    pub score: Option<String>,
+    /// The rank on the route, where tied athletes share the average of their
+    /// places. Routes that were not climbed yet have the average of all
+    /// places.
+    #[serde(default, deserialize_with = "lenient")]
+    pub corrective_rank: Option<f64>,
 }
 
-#[derive(Clone, Deserialize, Debug)]
+#[derive(Clone, Default, Deserialize, Debug, PartialEq)]
 pub struct SpeedAscent {
-    pub time_ms: u64,
+    #[serde(default, deserialize_with = "lenient")]
+    pub time_ms: Option<u64>,
 }
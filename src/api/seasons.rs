@@ -1,6 +1,8 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
+use chrono_tz::Tz;
 use dioxus::prelude::Props;
 use serde::Deserialize;
+use std::fmt::Display;
 
 #[derive(Deserialize)]
 pub struct SeasonsResponse {
@@ -11,50 +13,133 @@ pub struct SeasonsResponse {
 pub struct ShortSeason {
     pub id: u64,
     pub name: String,
-    // leagues: Vec<League>,
+    #[serde(default)]
+    pub leagues: Vec<League>,
 }
 
 #[derive(Deserialize)]
 pub struct Season {
     pub name: String,
-    // leagues: Vec<League>,
+    #[serde(default)]
+    pub leagues: Vec<League>,
     pub events: Vec<ShortEvent>,
 }
 
-// #[derive(Deserialize)]
-// pub struct League {}
+/// A series of events in a season, like the World Cups or the youth events
+#[derive(Clone, Deserialize, PartialEq)]
+pub struct League {
+    pub name: String,
+}
 
 #[derive(Clone, Deserialize, PartialEq, Props)]
 pub struct ShortEvent {
     pub event: String,
-    // location: String,
+    #[serde(default)]
+    pub location: String,
     pub event_id: u64,
     pub starts_at: DateTime<Utc>,
     pub ends_at: DateTime<Utc>,
-    // local_start_date: Date,
-    // local_end_date: Date,
-    // timezone: ...,
+    /// The first day of the event at the venue
+    #[serde(default)]
+    pub local_start_date: Option<NaiveDate>,
+    #[serde(default)]
+    pub local_end_date: Option<NaiveDate>,
+    #[serde(default)]
+    pub timezone: Option<Timezone>,
+    /// The name of the [`League`] the event is part of
+    #[serde(default)]
+    pub league_name: String,
+    #[serde(default)]
+    pub disciplines: Vec<EventDiscipline>,
 }
 
-#[derive(Deserialize)]
+#[derive(Clone, Deserialize, PartialEq)]
+pub struct EventDiscipline {
+    /// Like `boulder`
+    pub kind: String,
+}
+
+/// The timezone of the venue, like `Europe/Vienna`
+#[derive(Clone, Deserialize, PartialEq)]
+pub struct Timezone {
+    pub value: String,
+}
+
+impl Timezone {
+    /// `None` if the name is not in the timezone database
+    pub fn tz(&self) -> Option<Tz> {
+        self.value.parse().ok()
+    }
+}
+
+impl ShortEvent {
+    pub fn tz(&self) -> Option<Tz> {
+        self.timezone.as_ref().and_then(Timezone::tz)
+    }
+}
+
+#[derive(Clone, Deserialize)]
 pub struct Event {
-    // id: u64,
-    // name: String,
-    // location: String,
+    pub id: u64,
+    pub name: String,
+    #[serde(default)]
+    pub location: String,
     pub dcats: Vec<ShortCategory>,
-    // starts_at: DateTime<Utc>,
-    // ends_at: DateTime<Utc>,
-    // local_start_date: Date,
-    // local_end_date: Date,
-    // timezone: ...,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    #[serde(default)]
+    pub local_start_date: Option<NaiveDate>,
+    #[serde(default)]
+    pub local_end_date: Option<NaiveDate>,
+    #[serde(default)]
+    pub timezone: Option<Timezone>,
+}
+
+impl Event {
+    /// The event as it is listed in a season
+    pub fn short(&self) -> ShortEvent {
+        ShortEvent {
+            event: self.name.clone(),
+            location: self.location.clone(),
+            event_id: self.id,
+            starts_at: self.starts_at,
+            ends_at: self.ends_at,
+            local_start_date: self.local_start_date,
+            local_end_date: self.local_end_date,
+            timezone: self.timezone.clone(),
+            // Only the id of the league is in the event
+            league_name: String::new(),
+            disciplines: self.disciplines(),
+        }
+    }
+
+    /// The disciplines of the categories, without duplicates
+    fn disciplines(&self) -> Vec<EventDiscipline> {
+        let mut disciplines: Vec<EventDiscipline> = Vec::new();
+        for category in &self.dcats {
+            if !disciplines
+                .iter()
+                .any(|d| d.kind == category.discipline_kind)
+            {
+                disciplines.push(EventDiscipline {
+                    kind: category.discipline_kind.clone(),
+                });
+            }
+        }
+        disciplines
+    }
 }
 
 #[derive(Clone, Deserialize, PartialEq, Props)]
 pub struct ShortCategory {
     pub dcat_name: String,
-    category_name: String,
-    // discipline_kind: String,
-    // status: String,
+    pub category_name: String,
+    /// Like `boulder`
+    #[serde(default)]
+    pub discipline_kind: String,
+    /// Like `finished`
+    #[serde(default)]
+    pub status: String,
     pub category_rounds: Vec<CategoryRound>,
 }
 
@@ -62,4 +147,62 @@ pub struct ShortCategory {
 pub struct CategoryRound {
     pub category_round_id: u64,
     pub name: String,
+    /// The schedule of the round, which is only there for some events
+    #[serde(default)]
+    pub starts_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub ends_at: Option<DateTime<Utc>>,
+    /// Like `finished`
+    #[serde(default)]
+    pub status: String,
+}
+
+/// How far along a round is
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RoundStatus {
+    Upcoming,
+    Live,
+    Finished,
+}
+
+impl Display for RoundStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Upcoming => f.write_str("Upcoming"),
+            Self::Live => f.write_str("Live"),
+            Self::Finished => f.write_str("Finished"),
+        }
+    }
+}
+
+impl CategoryRound {
+    /// The status from the API, or from the schedule if the API doesn't give
+    /// a status we know. A round without either is finished once its event
+    /// has ended.
+    pub fn round_status(&self, event_ends_at: DateTime<Utc>, now: DateTime<Utc>) -> RoundStatus {
+        match self.status.as_str() {
+            "finished" => RoundStatus::Finished,
+            "active" => RoundStatus::Live,
+            "pending" => RoundStatus::Upcoming,
+            _ => match (self.starts_at, self.ends_at) {
+                (Some(start), _) if start > now => RoundStatus::Upcoming,
+                (_, Some(end)) if end < now => RoundStatus::Finished,
+                (Some(_), _) => RoundStatus::Live,
+                (None, _) if event_ends_at < now => RoundStatus::Finished,
+                (None, _) => RoundStatus::Upcoming,
+            },
+        }
+    }
+}
+
+impl Event {
+    /// The first round that is live, with the name of its category
+    pub fn live_round(&self, now: DateTime<Utc>) -> Option<(&ShortCategory, &CategoryRound)> {
+        self.dcats.iter().find_map(|c| {
+            c.category_rounds
+                .iter()
+                .find(|r| r.round_status(self.ends_at, now) == RoundStatus::Live)
+                .map(|r| (c, r))
+        })
+    }
 }
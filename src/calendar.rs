@@ -0,0 +1,310 @@
+//! A month or week calendar of the events of a season, with the times at the
+//! venue next to the times of the viewer
+use crate::api::{
+    self,
+    seasons::{Event as ApiEvent, Season, ShortEvent, ShortSeason},
+};
+use crate::{export, filter::Filter, ical, leaderboard::LeaderboardInput, Page};
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, Utc};
+use chrono_tz::Tz;
+use dioxus::prelude::*;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Span {
+    Month,
+    Week,
+}
+
+/// The days shown for the span around the given day. Both start on a Monday
+/// and a month is padded to whole weeks.
+pub fn days(span: Span, day: NaiveDate) -> Vec<NaiveDate> {
+    let (first, last) = match span {
+        Span::Month => {
+            let first = day.with_day(1).unwrap();
+            let next = next_month(first);
+            (
+                monday(first),
+                monday(next - Duration::days(1)) + Duration::days(6),
+            )
+        }
+        Span::Week => (monday(day), monday(day) + Duration::days(6)),
+    };
+    first.iter_days().take_while(|d| *d <= last).collect()
+}
+
+/// The same day in the next or previous span
+pub fn step(span: Span, day: NaiveDate, forward: bool) -> NaiveDate {
+    match (span, forward) {
+        (Span::Month, true) => next_month(day.with_day(1).unwrap()),
+        (Span::Month, false) => (day.with_day(1).unwrap() - Duration::days(1))
+            .with_day(1)
+            .unwrap(),
+        (Span::Week, true) => day + Duration::days(7),
+        (Span::Week, false) => day - Duration::days(7),
+    }
+}
+
+fn monday(day: NaiveDate) -> NaiveDate {
+    day - Duration::days(day.weekday().num_days_from_monday().into())
+}
+
+fn next_month(first: NaiveDate) -> NaiveDate {
+    (first + Duration::days(31)).with_day(1).unwrap()
+}
+
+/// The first and last day of the event at the venue. Older events don't have
+/// local dates, so we fall back to the dates in UTC.
+pub fn local_dates(event: &ShortEvent) -> (NaiveDate, NaiveDate) {
+    (
+        event
+            .local_start_date
+            .unwrap_or_else(|| event.starts_at.date_naive()),
+        event
+            .local_end_date
+            .unwrap_or_else(|| event.ends_at.date_naive()),
+    )
+}
+
+/// The events that take place on the day, at the venue
+pub fn events_on(events: &[ShortEvent], day: NaiveDate) -> Vec<&ShortEvent> {
+    events
+        .iter()
+        .filter(|e| {
+            let (start, end) = local_dates(e);
+            start <= day && day <= end
+        })
+        .collect()
+}
+
+/// The time at the venue, with the abbreviation of its timezone. Without a
+/// known timezone, the time is shown in UTC.
+pub fn venue_time(at: DateTime<Utc>, tz: Option<Tz>) -> String {
+    match tz {
+        Some(tz) => at.with_timezone(&tz).format("%b %e %H:%M %Z").to_string(),
+        None => at.format("%b %e %H:%M UTC").to_string(),
+    }
+}
+
+/// The time for the person looking at the calendar
+pub fn viewer_time(at: DateTime<Utc>) -> String {
+    at.with_timezone(&Local).format("%b %e %H:%M").to_string()
+}
+
+pub fn Calendar(cx: Scope<ShortSeason>) -> Element {
+    let url = format!("seasons/{}", cx.props.id);
+    let future = use_future(cx, (&cx.props.id,), |_| api::request::<Season>(url));
+    let span = use_state(cx, || Span::Month);
+    let day = use_state(cx, || Local::now().date_naive());
+    let selected = use_state(cx, || None::<ShortEvent>);
+
+    let events = match future.value() {
+        Some(Some(season)) => &season.events,
+        Some(None) => return cx.render(rsx! { "Could not load the season" }),
+        None => return cx.render(rsx! { "Loading..." }),
+    };
+
+    let title = match span.get() {
+        Span::Month => day.format("%B %Y").to_string(),
+        Span::Week => format!("Week of {}", monday(*day.get()).format("%b %e %Y")),
+    };
+
+    let days = days(*span.get(), *day.get());
+    let weeks = days.chunks(7).map(|week| {
+        let cells = week.iter().map(|d| {
+            let class = if d.month() == day.month() || *span.get() == Span::Week {
+                ""
+            } else {
+                "other-month"
+            };
+            let number = d.day();
+            let events = events_on(events, *d).into_iter().map(|e| {
+                let event = e.clone();
+                rsx! {
+                    div {
+                        class: "calendar-event",
+                        onclick: move |_| selected.set(Some(event.clone())),
+                        "{e.location}"
+                    }
+                }
+            });
+            rsx! {
+                td {
+                    class: "{class}",
+                    div { class: "day", "{number}" }
+                    events
+                }
+            }
+        });
+        rsx! { tr { cells } }
+    });
+
+    let span_button = |s: Span, label: &'static str| {
+        rsx! {
+            button {
+                class: if *span.get() == s { "selected" } else { "" },
+                onclick: move |_| span.set(s),
+                "{label}"
+            }
+        }
+    };
+
+    cx.render(rsx! {
+        h1 { "{cx.props.name} calendar" }
+        div {
+            class: "actions",
+            span_button(Span::Month, "Month")
+            span_button(Span::Week, "Week")
+            button { onclick: move |_| day.set(step(*span.get(), *day.get(), false)), "◀" }
+            button { onclick: move |_| day.set(Local::now().date_naive()), "Today" }
+            button { onclick: move |_| day.set(step(*span.get(), *day.get(), true)), "▶" }
+        }
+        h2 { "{title}" }
+        table {
+            class: "calendar",
+            thead {
+                tr {
+                    ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"].iter().map(|d| rsx! { th { "{d}" } })
+                }
+            }
+            tbody { weeks }
+        }
+        if let Some(event) = selected.get() {
+            rsx! { Schedule { ..event.clone() } }
+        }
+    })
+}
+
+/// The times of an event and its rounds, at the venue and for the viewer
+fn Schedule(cx: Scope<ShortEvent>) -> Element {
+    let url = format!("events/{}", cx.props.event_id);
+    let future = use_future(cx, (&cx.props.event_id,), |_| api::request::<ApiEvent>(url));
+    let page = use_shared_state::<Page>(cx).unwrap();
+    let export_status = use_state(cx, || None::<String>);
+    let tz = cx.props.tz();
+    let zone = cx
+        .props
+        .timezone
+        .as_ref()
+        .map_or("UTC".to_string(), |t| t.value.clone());
+
+    let row = |name: String, starts_at: DateTime<Utc>, ends_at: Option<DateTime<Utc>>| {
+        let venue = venue_time(starts_at, tz);
+        let viewer = viewer_time(starts_at);
+        let (venue_end, viewer_end) = match ends_at {
+            Some(end) => (
+                format!(" - {}", venue_time(end, tz)),
+                format!(" - {}", viewer_time(end)),
+            ),
+            None => (String::new(), String::new()),
+        };
+        rsx! {
+            tr {
+                td { "{name}" }
+                td { "{venue}{venue_end}" }
+                td { "{viewer}{viewer_end}" }
+            }
+        }
+    };
+
+    let (rounds, hint) = match future.value() {
+        Some(Some(event)) => {
+            let rounds: Vec<_> = event
+                .dcats
+                .iter()
+                .flat_map(|c| c.category_rounds.iter().map(move |r| (c, r)))
+                .filter_map(|(c, r)| {
+                    Some((
+                        format!("{} {}", c.dcat_name, r.name),
+                        r.starts_at?,
+                        r.ends_at,
+                        r.category_round_id,
+                    ))
+                })
+                .collect();
+            let hint = rounds
+                .is_empty()
+                .then_some("There is no schedule of the rounds yet");
+            (rounds, hint)
+        }
+        Some(None) => (Vec::new(), Some("Could not load the rounds")),
+        None => (Vec::new(), Some("Loading...")),
+    };
+
+    let rounds = rounds.into_iter().map(|(name, starts_at, ends_at, round_id)| {
+        let event_name = cx.props.event.clone();
+        rsx! {
+            tbody {
+                class: "round",
+                onclick: move |_| *page.write() = Page::Leaderboard(LeaderboardInput::Api(event_name.clone(), round_id)),
+                row(name, starts_at, ends_at)
+            }
+        }
+    });
+
+    let ics = future.value().and_then(Option::as_ref).map(|event| {
+        let entry = ical::Entry {
+            event: cx.props.clone(),
+            details: Some(event.clone()),
+        };
+        ical::calendar(&cx.props.event, &[entry], &Filter::default())
+    });
+    let file_name = format!("{}.ics", export::safe_file_name(&cx.props.event));
+
+    // In the browser we can only offer a download, on desktop we write the
+    // file to the export directory.
+    #[cfg(feature = "web")]
+    let export = ics.map(|ics| {
+        let href = export::data_url("text/calendar", &ics);
+        rsx! {
+            div {
+                class: "export",
+                a { href: "{href}", download: "{file_name}", "Export calendar" }
+            }
+        }
+    });
+    #[cfg(feature = "desktop")]
+    let export = ics.map(|ics| {
+        let path = std::path::PathBuf::from("export").join(file_name);
+        let onclick = move |_| {
+            let written =
+                std::fs::create_dir_all("export").and_then(|_| std::fs::write(&path, &ics));
+            export_status.set(Some(match written {
+                Ok(()) => format!("Exported to {}", path.display()),
+                Err(e) => format!("Could not export: {e}"),
+            }));
+        };
+        rsx! {
+            div {
+                class: "export",
+                button { onclick: onclick, "Export calendar" }
+                if let Some(status) = export_status.get() {
+                    rsx! { div { class: "export-status", "{status}" } }
+                }
+            }
+        }
+    });
+
+    let (start, end) = local_dates(cx.props);
+    let dates = format!("{} - {}", start.format("%b %e"), end.format("%b %e"));
+
+    cx.render(rsx! {
+        h2 { "{cx.props.event}" }
+        div { "{cx.props.location} | {dates} | {zone}" }
+        table {
+            class: "standings schedule",
+            thead {
+                tr {
+                    th {}
+                    th { "At the venue" }
+                    th { "Your time" }
+                }
+            }
+            tbody { row("Event".to_string(), cx.props.starts_at, Some(cx.props.ends_at)) }
+            rounds
+        }
+        if let Some(hint) = hint {
+            rsx! { div { class: "hint", "{hint}" } }
+        }
+        export
+    })
+}
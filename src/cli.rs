@@ -0,0 +1,466 @@
+//! Commands for querying the API without launching the UI
+use crate::api::{
+    self,
+    result::{DisciplineTag, Results},
+    seasons::{Event, Season, SeasonsResponse},
+};
+use crate::export;
+use crate::filter::Filter;
+use crate::head_to_head;
+use crate::history::{self, History};
+use crate::ical;
+use crate::rating::Ratings;
+use crate::standings;
+use crate::table::{Format, Table};
+use clap::Subcommand;
+use std::path::PathBuf;
+
+#[derive(Subcommand, Debug, Clone, PartialEq)]
+pub enum Command {
+    /// List all seasons
+    Seasons,
+    /// List the events of a season
+    Events {
+        #[arg(long)]
+        season: u64,
+    },
+    /// List the category rounds of an event
+    Rounds {
+        #[arg(long)]
+        event: u64,
+    },
+    /// Print the ranking of a category round, as computed by us
+    Results {
+        #[arg(long)]
+        round: u64,
+    },
+    /// Print the World Cup ranking of a category over the finished events of a
+    /// season
+    Standings {
+        #[arg(long)]
+        season: u64,
+        /// The category as listed by `rounds`, like "BOULDER Men"
+        #[arg(long)]
+        category: String,
+        /// Only count the best results of every athlete
+        #[arg(long)]
+        best: Option<usize>,
+        /// Use the results stored by `sync` instead of the API
+        #[arg(long)]
+        offline: bool,
+    },
+    /// Store the results of a range of seasons in the local database, so
+    /// they can be queried without network
+    Sync {
+        #[arg(long)]
+        from: u64,
+        /// The last season to store, the same as `from` if not given
+        #[arg(long)]
+        to: Option<u64>,
+    },
+    /// Print every stored round of an athlete, from the oldest to the newest
+    History {
+        #[arg(long)]
+        athlete: u64,
+        /// Print the statistics of every discipline instead of the rounds
+        #[arg(long)]
+        statistics: bool,
+    },
+    /// Print the best rated athletes of a discipline, rated by the stored
+    /// rounds
+    Ratings {
+        #[arg(long, value_enum)]
+        discipline: DisciplineTag,
+        #[arg(long, default_value_t = 20)]
+        top: usize,
+    },
+    /// Predict the ranking of a round from the ratings of its athletes
+    Predict {
+        #[arg(long)]
+        round: u64,
+    },
+    /// Compare two athletes in every round in which both of them climbed
+    HeadToHead {
+        #[arg(long)]
+        first: u64,
+        #[arg(long)]
+        second: u64,
+    },
+    /// Export the ranking of a category round to a csv, xlsx or ods file
+    Export {
+        #[arg(long)]
+        round: u64,
+        /// The format is determined by the extension
+        #[arg(short, long)]
+        output: PathBuf,
+    },
+    /// Write the events of a season or a single event, with the schedule of
+    /// their rounds, as an iCalendar file
+    Calendar {
+        #[arg(long, required_unless_present = "event", conflicts_with = "event")]
+        season: Option<u64>,
+        #[arg(long)]
+        event: Option<u64>,
+        /// Only the rounds of this discipline, like "boulder"
+        #[arg(long)]
+        discipline: Option<String>,
+        /// Only the events of this league, like "IFSC Youth". A single event
+        /// doesn't know its league, so this only works with a season.
+        #[arg(long, conflicts_with = "event")]
+        league: Option<String>,
+        /// Only the rounds of this category, like "Women"
+        #[arg(long)]
+        category: Option<String>,
+        /// Print the calendar if no file is given
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Serve the computed rankings as a JSON API
+    #[cfg(feature = "server")]
+    Serve {
+        #[arg(long, default_value_t = 3000)]
+        port: u16,
+    },
+}
+
+pub fn run(command: Command, format: Format) {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+
+    #[cfg(feature = "server")]
+    if let Command::Serve { port } = command {
+        runtime.block_on(crate::server::serve(port));
+        return;
+    }
+
+    if let Command::Sync { from, to } = command {
+        let history = open_history();
+        // Every season is synced even if one fails, the exit status tells
+        // whether anything is missing
+        let mut failed = false;
+        for season in from..=to.unwrap_or(from) {
+            match runtime.block_on(history.sync_season(season)) {
+                Ok(Some(synced)) => {
+                    eprintln!(
+                        "Season {season}: stored {} events with {} rounds",
+                        synced.events, synced.rounds
+                    );
+                    if synced.failed_events > 0 {
+                        eprintln!(
+                            "Season {season}: could not load {} events",
+                            synced.failed_events
+                        );
+                        failed = true;
+                    }
+                }
+                Ok(None) => {
+                    eprintln!("Season {season}: could not load the season");
+                    failed = true;
+                }
+                Err(e) => {
+                    eprintln!("Could not store season {season}: {e}");
+                    std::process::exit(1);
+                }
+            }
+        }
+        if failed {
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if let Command::Calendar {
+        season,
+        event,
+        discipline,
+        league,
+        category,
+        output,
+    } = command
+    {
+        let filter = Filter {
+            discipline,
+            league,
+            category,
+        };
+        let calendar = runtime.block_on(async {
+            let (name, entries) = match (season, event) {
+                (Some(season), _) => ical::fetch_season(season).await?,
+                (None, Some(event)) => {
+                    let entry = ical::fetch_event(event).await?;
+                    (entry.event.event.clone(), vec![entry])
+                }
+                (None, None) => unreachable!("clap requires a season or an event"),
+            };
+            Some(ical::calendar(&name, &entries, &filter))
+        });
+        let Some(calendar) = calendar else {
+            eprintln!("Could not load the data from the API");
+            std::process::exit(1);
+        };
+        match output {
+            Some(path) => {
+                if let Err(e) = std::fs::write(&path, calendar) {
+                    eprintln!("Could not write {}: {e}", path.display());
+                    std::process::exit(1);
+                }
+            }
+            None => print!("{calendar}"),
+        }
+        return;
+    }
+
+    let output = match &command {
+        Command::Export { output, .. } => Some(output.clone()),
+        _ => None,
+    };
+
+    let Some(table) = runtime.block_on(fetch_table(command)) else {
+        eprintln!("Could not load the data from the API");
+        std::process::exit(1);
+    };
+
+    match output {
+        Some(path) => {
+            if let Err(e) = export::write(&table, &path) {
+                eprintln!("Could not write {}: {e}", path.display());
+                std::process::exit(1);
+            }
+        }
+        None => print!("{}", table.render(format)),
+    }
+}
+
+async fn fetch_table(command: Command) -> Option<Table> {
+    Some(match command {
+        Command::Seasons => {
+            let SeasonsResponse { seasons } =
+                api::request::<SeasonsResponse>(String::new()).await?;
+            let mut table = Table::new(["id", "name"]);
+            for s in seasons {
+                table.push(vec![s.id.to_string(), s.name]);
+            }
+            table
+        }
+        Command::Events { season } => {
+            let season: Season = api::request(format!("seasons/{season}")).await?;
+            let mut events = season.events;
+            events.sort_by_key(|e| e.starts_at);
+            let mut table = Table::new(["id", "name", "starts_at", "ends_at"]);
+            for e in events {
+                table.push(vec![
+                    e.event_id.to_string(),
+                    e.event,
+                    e.starts_at.to_rfc3339(),
+                    e.ends_at.to_rfc3339(),
+                ]);
+            }
+            table
+        }
+        Command::Rounds { event } => {
+            let event: Event = api::request(format!("events/{event}")).await?;
+            let mut table = Table::new(["id", "category", "round"]);
+            for c in event.dcats {
+                for r in c.category_rounds {
+                    table.push(vec![
+                        r.category_round_id.to_string(),
+                        c.dcat_name.clone(),
+                        r.name,
+                    ]);
+                }
+            }
+            table
+        }
+        Command::Results { round } | Command::Export { round, .. } => {
+            let results: Results =
+                api::request(format!("category_rounds/{round}/results/")).await?;
+            export::results_table(&results)
+        }
+        Command::Standings {
+            season,
+            category,
+            best,
+            offline,
+        } => {
+            let categories = if offline {
+                match open_history().season_categories(season) {
+                    Ok(categories) => categories,
+                    Err(e) => {
+                        eprintln!("Could not read the database: {e}");
+                        std::process::exit(1);
+                    }
+                }
+            } else {
+                standings::fetch_season(season).await?
+            };
+            let Some(category) = categories.into_iter().find(|c| c.name == category) else {
+                eprintln!("No results for {category} in this season");
+                std::process::exit(1);
+            };
+            let mut headers: Vec<String> = [
+                "rank",
+                "athlete_id",
+                "first_name",
+                "last_name",
+                "country",
+                "points",
+            ]
+            .into_iter()
+            .map(String::from)
+            .collect();
+            headers.extend(category.events.iter().map(|e| e.event.clone()));
+            let mut table = Table::new(headers);
+            for s in standings::standings(&category.events, best) {
+                let mut row = vec![
+                    s.rank.to_string(),
+                    s.athlete.athlete_id.to_string(),
+                    s.athlete.firstname,
+                    s.athlete.lastname,
+                    s.athlete.country.country,
+                    s.points.to_string(),
+                ];
+                // Points that don't count for the total are in parentheses
+                row.extend(s.events.into_iter().map(|e| match e {
+                    Some(e) if e.counted => e.points.to_string(),
+                    Some(e) => format!("({})", e.points),
+                    None => String::new(),
+                }));
+                table.push(row);
+            }
+            table
+        }
+        Command::HeadToHead { first, second } => {
+            let comparison = head_to_head::compare(first, second).await?;
+            head_to_head::table(&comparison)
+        }
+        Command::History {
+            athlete,
+            statistics,
+        } => {
+            let entries = match open_history().athlete_history(athlete) {
+                Ok(entries) => entries,
+                Err(e) => {
+                    eprintln!("Could not read the database: {e}");
+                    std::process::exit(1);
+                }
+            };
+            if statistics {
+                let mut table = Table::new([
+                    "discipline",
+                    "rounds",
+                    "finals",
+                    "wins",
+                    "podiums",
+                    "best_rank",
+                ]);
+                for s in history::statistics(&entries) {
+                    table.push(vec![
+                        s.discipline.to_string(),
+                        s.rounds.to_string(),
+                        s.finals.to_string(),
+                        s.wins.to_string(),
+                        s.podiums.to_string(),
+                        s.best_rank.map_or(String::new(), |r| r.to_string()),
+                    ]);
+                }
+                table
+            } else {
+                let mut table = Table::new([
+                    "season",
+                    "event",
+                    "starts_at",
+                    "category",
+                    "round",
+                    "round_id",
+                    "rank",
+                ]);
+                for e in entries {
+                    table.push(vec![
+                        e.season,
+                        e.event,
+                        e.starts_at.to_rfc3339(),
+                        e.category,
+                        e.round,
+                        e.round_id.to_string(),
+                        e.rank.map_or(String::new(), |r| r.to_string()),
+                    ]);
+                }
+                table
+            }
+        }
+        Command::Ratings { discipline, top } => {
+            let ratings = open_ratings();
+            let mut table = Table::new([
+                "rank",
+                "athlete_id",
+                "first_name",
+                "last_name",
+                "country",
+                "rating",
+                "rounds",
+            ]);
+            for (i, t) in ratings.top(&discipline, top).into_iter().enumerate() {
+                table.push(vec![
+                    (i + 1).to_string(),
+                    t.athlete.athlete_id.to_string(),
+                    t.athlete.firstname.clone(),
+                    t.athlete.lastname.clone(),
+                    t.athlete.country.country.clone(),
+                    format!("{:.0}", t.current()),
+                    t.points.len().to_string(),
+                ]);
+            }
+            table
+        }
+        Command::Predict { round } => {
+            let results: Results = api::request(format!("category_rounds/{round}/results")).await?;
+            let athletes: Vec<_> = results.ranking.into_iter().map(|a| a.athlete).collect();
+            let mut table = Table::new([
+                "rank",
+                "athlete_id",
+                "first_name",
+                "last_name",
+                "country",
+                "rating",
+            ]);
+            for p in open_ratings().predict(&results.discipline, &athletes) {
+                table.push(vec![
+                    p.rank.to_string(),
+                    p.athlete.athlete_id.to_string(),
+                    p.athlete.firstname,
+                    p.athlete.lastname,
+                    p.athlete.country.country,
+                    format!("{:.0}", p.rating),
+                ]);
+            }
+            table
+        }
+        Command::Calendar { .. } => unreachable!("the calendar is not a table"),
+        Command::Sync { .. } => unreachable!("syncing does not produce a table"),
+        #[cfg(feature = "server")]
+        Command::Serve { .. } => unreachable!("the server does not produce a table"),
+    })
+}
+
+fn open_ratings() -> Ratings {
+    match open_history().ratings() {
+        Ok(ratings) => ratings,
+        Err(e) => {
+            eprintln!("Could not read the database: {e}");
+            std::process::exit(1);
+        }
+    }
+}
+
+fn open_history() -> History {
+    let path = std::path::Path::new(history::DEFAULT_PATH);
+    match History::open(path) {
+        Ok(history) => history,
+        Err(e) => {
+            eprintln!("Could not open {}: {e}", path.display());
+            std::process::exit(1);
+        }
+    }
+}
@@ -1,35 +1,108 @@
 //! Types that define a discpline
 use crate::api;
 use dioxus::prelude::LazyNodes;
+use std::fmt::Display;
 
 pub use boulder::*;
 pub use lead::*;
 pub use speed::*;
+pub use unknown::*;
 
 pub trait Discipline {
     type Ascent: Ascent;
     type Score: Score<Ascent = Self::Ascent>;
 }
 
-pub trait Ascent: TryFrom<api::result::Ascent> {
+/// The `Display` implementation is used for the textual output, such as the
+/// command line.
+pub trait Ascent: TryFrom<api::result::Ascent, Error = MissingField> + Display {
     fn render(&self) -> LazyNodes;
 }
 
+/// The field of an ascent that is missing or could not be read, the reason an
+/// ascent of the API could not be converted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MissingField(pub &'static str);
+
+impl Display for MissingField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "missing or invalid `{}`", self.0)
+    }
+}
+
 pub trait Score: Ord {
     type Ascent: Ascent;
 
+    /// Names of the values that make up the score
+    const FIELDS: &'static [&'static str];
+
+    /// Whether the score is calculated like the official score, so that our
+    /// ranking can be compared with the official one
+    const COMPARABLE: bool = true;
+
     fn render(&self) -> LazyNodes;
-    fn calculate(start_order: u64, ascents: &[Self::Ascent]) -> Self;
+
+    /// The score of the ascents on every route, routes with an ascent that
+    /// could not be read are `None`
+    fn calculate(start_order: u64, ascents: &[Option<Self::Ascent>]) -> Self;
+
+    /// The values of the score, in the same order as [`Score::FIELDS`]
+    fn values(&self) -> Vec<String>;
+
+    /// Parse a score in the notation of the official results, such as
+    /// `3T4z 6 7` for boulder or `1.58` for lead.
+    fn parse_official(notation: &str) -> Option<Self>
+    where
+        Self: Sized;
+
+    /// The score in the notation of the official results, the inverse of
+    /// [`Score::parse_official`]
+    fn official(&self) -> String;
+}
+
+/// Parse a decimal number like `1.58` into an integer with the given number of
+/// decimals, `158` in this case.
+fn parse_fixed(s: &str, decimals: u32) -> Option<u64> {
+    let (whole, fraction) = s.split_once('.').unwrap_or((s, ""));
+    let digits = decimals as usize;
+    let is_number = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+    if whole.is_empty() || fraction.len() > digits || !is_number(whole) || !is_number(fraction)
+    {
+        return None;
+    }
+    let whole: u64 = whole.parse().ok()?;
+    let fraction: u64 = format!("{fraction:0<digits$}").parse().ok()?;
+    Some(whole * 10u64.pow(decimals) + fraction)
+}
+
+/// Format an integer with the given number of decimals, the inverse of
+/// [`parse_fixed`].
+fn format_fixed(value: u64, decimals: u32) -> String {
+    let factor = 10u64.pow(decimals);
+    format!(
+        "{}.{:0width$}",
+        value / factor,
+        value % factor,
+        width = decimals as usize
+    )
 }
 
 mod lead {
     use std::cmp::Ordering;
+    use std::fmt::Display;
 
-    use super::{Ascent, Discipline, Score};
+    use super::{format_fixed, parse_fixed, Ascent, Discipline, MissingField, Score};
     use crate::api;
     use dioxus::prelude::{rsx, LazyNodes};
     use serde::Deserialize;
 
+    /// Lead rounds on several routes, like qualifications and the paraclimbing
+    /// rounds with 2 routes, are scored by the geometric mean of the ranks on
+    /// the routes. Rounds on a single route are ranked by the rank on that
+    /// route.
+    ///
+    /// The rank on a route depends on the heights of all athletes, so we take
+    /// it from the API instead of comparing the heights ourselves.
     #[derive(Debug)]
     pub struct Lead;
 
@@ -41,6 +114,7 @@ mod lead {
     #[derive(Debug, Deserialize)]
     pub struct LeadAscent {
         score: String,
+        corrective_rank: Option<f64>,
     }
 
     impl Ascent for LeadAscent {
@@ -49,30 +123,60 @@ mod lead {
         }
     }
 
+    impl Display for LeadAscent {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.write_str(&self.score)
+        }
+    }
+
     impl TryFrom<api::result::Ascent> for LeadAscent {
-        type Error = ();
+        type Error = MissingField;
 
         fn try_from(value: api::result::Ascent) -> Result<Self, Self::Error> {
-            if let Some(api::result::LeadAscent { score }) = value.lead {
-                Ok(Self { score })
-            } else {
-                Err(())
-            }
+            let score = value.lead.score.ok_or(MissingField("score"))?;
+            Ok(Self {
+                score,
+                corrective_rank: value.lead.corrective_rank,
+            })
         }
     }
 
+    /// The points of the athlete in hundredths, lower is better. Athletes
+    /// without a rank on any route have no points.
     #[derive(PartialEq, Eq, Debug, Clone)]
-    pub struct LeadScore(u64);
+    pub struct LeadScore(Option<u64>);
 
     impl Score for LeadScore {
         type Ascent = LeadAscent;
+        const FIELDS: &'static [&'static str] = &["score"];
+
         fn render(&self) -> LazyNodes {
-            rsx! { self.0.to_string() }
+            rsx! { self.official() }
         }
 
-        fn calculate(_: u64, _: &[Self::Ascent]) -> Self {
-            // FIXME: Implement calculation
-            LeadScore(0)
+        fn calculate(_: u64, ascents: &[Option<Self::Ascent>]) -> Self {
+            let ranks: Vec<f64> = ascents
+                .iter()
+                .flatten()
+                .filter_map(|a| a.corrective_rank)
+                .collect();
+            if ranks.is_empty() {
+                return LeadScore(None);
+            }
+            let mean = ranks.iter().product::<f64>().powf(1.0 / ranks.len() as f64);
+            LeadScore(Some((mean * 100.0).round() as u64))
+        }
+
+        fn values(&self) -> Vec<String> {
+            vec![self.official()]
+        }
+
+        fn parse_official(notation: &str) -> Option<Self> {
+            parse_fixed(notation.trim(), 2).map(|points| LeadScore(Some(points)))
+        }
+
+        fn official(&self) -> String {
+            self.0.map_or(String::new(), |points| format_fixed(points, 2))
         }
     }
 
@@ -84,7 +188,12 @@ mod lead {
 
     impl Ord for LeadScore {
         fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-            self.0.cmp(&other.0)
+            match (self.0, other.0) {
+                (Some(a), Some(b)) => a.cmp(&b).reverse(),
+                (Some(_), None) => Ordering::Greater,
+                (None, Some(_)) => Ordering::Less,
+                (None, None) => Ordering::Equal,
+            }
         }
     }
 }
@@ -93,10 +202,11 @@ mod boulder {
     use dioxus::prelude::*;
     use serde::Deserialize;
     use std::cmp::Ordering;
+    use std::fmt::Display;
 
     use crate::api::{self, result::Status};
 
-    use super::{Ascent, Discipline, Score};
+    use super::{Ascent, Discipline, MissingField, Score};
 
     #[derive(Debug)]
     pub struct Boulder;
@@ -139,27 +249,35 @@ mod boulder {
         }
     }
 
+    impl Display for BoulderAscent {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            if self.top {
+                write!(f, "T{} Z{}", self.top_tries, self.zone_tries)
+            } else if self.zone {
+                write!(f, "Z{}", self.zone_tries)
+            } else {
+                f.write_str("-")
+            }
+        }
+    }
+
     impl TryFrom<api::result::Ascent> for BoulderAscent {
-        type Error = ();
+        type Error = MissingField;
 
         fn try_from(value: api::result::Ascent) -> Result<Self, Self::Error> {
-            if let Some(api::result::BoulderAscent {
+            let api::result::BoulderAscent {
                 top,
                 top_tries,
                 zone,
                 zone_tries,
-            }) = value.boulder
-            {
-                Ok(Self {
-                    top,
-                    top_tries: top_tries.unwrap_or_default(),
-                    zone,
-                    zone_tries: zone_tries.unwrap_or_default(),
-                    status: value.status,
-                })
-            } else {
-                Err(())
-            }
+            } = value.boulder;
+            Ok(Self {
+                top: top.ok_or(MissingField("top"))?,
+                top_tries: top_tries.unwrap_or_default(),
+                zone: zone.ok_or(MissingField("zone"))?,
+                zone_tries: zone_tries.unwrap_or_default(),
+                status: value.status,
+            })
         }
     }
 
@@ -169,11 +287,13 @@ mod boulder {
         tops: u64,
         zones: u64,
         top_tries: u64,
+        zone_tries: u64,
         start_order: u64,
     }
 
     impl Score for BoulderScore {
         type Ascent = BoulderAscent;
+        const FIELDS: &'static [&'static str] = &["tops", "zones", "top_tries", "zone_tries"];
 
         fn render(&self) -> LazyNodes {
             rsx! {
@@ -183,18 +303,54 @@ mod boulder {
             }
         }
 
-        fn calculate(start_order: u64, ascents: &[Self::Ascent]) -> Self {
+        fn calculate(start_order: u64, ascents: &[Option<Self::Ascent>]) -> Self {
+            let ascents: Vec<_> = ascents.iter().flatten().cloned().collect();
             Self {
-                ascents: ascents.to_vec(),
                 tops: ascents.iter().filter(|a| a.top).count() as u64,
                 zones: ascents.iter().filter(|a| a.zone).count() as u64,
                 top_tries: ascents
                     .iter()
                     .map(|a| if a.top { a.top_tries } else { 0 })
                     .sum(),
+                zone_tries: ascents
+                    .iter()
+                    .map(|a| if a.zone { a.zone_tries } else { 0 })
+                    .sum(),
                 start_order,
+                ascents,
             }
         }
+
+        fn values(&self) -> Vec<String> {
+            vec![
+                self.tops.to_string(),
+                self.zones.to_string(),
+                self.top_tries.to_string(),
+                self.zone_tries.to_string(),
+            ]
+        }
+
+        fn parse_official(notation: &str) -> Option<Self> {
+            let mut parts = notation.split_whitespace();
+            let (tops, zones) = parts.next()?.split_once('T')?;
+            let zones = zones.strip_suffix('z')?;
+            let score = Self {
+                ascents: Vec::new(),
+                tops: tops.parse().ok()?,
+                zones: zones.parse().ok()?,
+                top_tries: parts.next()?.parse().ok()?,
+                zone_tries: parts.next()?.parse().ok()?,
+                start_order: 0,
+            };
+            parts.next().is_none().then_some(score)
+        }
+
+        fn official(&self) -> String {
+            format!(
+                "{}T{}z {} {}",
+                self.tops, self.zones, self.top_tries, self.zone_tries
+            )
+        }
     }
 
     impl PartialOrd for BoulderScore {
@@ -209,6 +365,7 @@ mod boulder {
                 .then(self.tops.cmp(&other.tops))
                 .then(self.zones.cmp(&other.zones))
                 .then(self.top_tries.cmp(&other.top_tries).reverse())
+                .then(self.zone_tries.cmp(&other.zone_tries).reverse())
                 .then(self.start_order.cmp(&other.start_order))
         }
     }
@@ -221,10 +378,15 @@ mod boulder {
 }
 
 mod speed {
-    use super::{Ascent, Discipline, Score};
+    use super::{format_fixed, parse_fixed, Ascent, Discipline, MissingField, Score};
     use crate::api;
     use dioxus::prelude::*;
+    use std::cmp::Ordering;
+    use std::fmt::Display;
 
+    /// Speed rounds are ranked by the fastest time. The finals are knockout
+    /// heats, where the winner of a heat goes through regardless of the times
+    /// in other heats, so our ranking can't be compared with the official one.
     #[derive(Debug)]
     pub struct Speed;
 
@@ -238,14 +400,17 @@ mod speed {
     }
 
     impl TryFrom<api::result::Ascent> for SpeedAscent {
-        type Error = ();
+        type Error = MissingField;
 
         fn try_from(value: api::result::Ascent) -> Result<Self, Self::Error> {
-            if let Some(api::result::SpeedAscent { time_ms }) = value.speed {
-                Ok(Self { time_ms })
-            } else {
-                Err(())
-            }
+            let time_ms = value.speed.time_ms.ok_or(MissingField("time_ms"))?;
+            Ok(Self { time_ms })
+        }
+    }
+
+    impl Display for SpeedAscent {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{}", self.time_ms)
         }
     }
 
@@ -255,21 +420,129 @@ mod speed {
         }
     }
 
-    #[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+    /// The fastest time, athletes without a time are ranked last
+    #[derive(Clone, PartialEq, Eq)]
     pub struct SpeedScore {
-        time_ms: u64
+        time_ms: Option<u64>,
     }
 
     impl Score for SpeedScore {
         type Ascent = SpeedAscent;
+        const FIELDS: &'static [&'static str] = &["time_ms"];
+        const COMPARABLE: bool = false;
 
         fn render(&self) -> LazyNodes {
-            rsx! { div { "{self.time_ms}" } }
+            let time_ms = self.time_ms.map_or(String::new(), |t| t.to_string());
+            rsx! { div { "{time_ms}" } }
         }
 
-        fn calculate(_start_order: u64, ascents: &[Self::Ascent]) -> Self {
-            let time_ms = ascents.iter().map(|a| a.time_ms).min().unwrap_or(0);
+        fn calculate(_start_order: u64, ascents: &[Option<Self::Ascent>]) -> Self {
+            let time_ms = ascents
+                .iter()
+                .flatten()
+                .map(|a| a.time_ms)
+                .min();
             Self { time_ms }
         }
+
+        fn values(&self) -> Vec<String> {
+            vec![self.time_ms.map_or(String::new(), |t| t.to_string())]
+        }
+
+        fn parse_official(notation: &str) -> Option<Self> {
+            parse_fixed(notation.trim(), 3).map(|time_ms| Self {
+                time_ms: Some(time_ms),
+            })
+        }
+
+        fn official(&self) -> String {
+            self.time_ms.map_or(String::new(), |t| format_fixed(t, 3))
+        }
+    }
+
+    impl PartialOrd for SpeedScore {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl Ord for SpeedScore {
+        fn cmp(&self, other: &Self) -> Ordering {
+            match (self.time_ms, other.time_ms) {
+                (Some(a), Some(b)) => a.cmp(&b).reverse(),
+                (Some(_), None) => Ordering::Greater,
+                (None, Some(_)) => Ordering::Less,
+                (None, None) => Ordering::Equal,
+            }
+        }
+    }
+}
+
+/// A fallback for disciplines we don't know, which shows the athletes in the
+/// order of the API without ascents or scores.
+mod unknown {
+    use super::{Ascent, Discipline, MissingField, Score};
+    use crate::api;
+    use dioxus::prelude::*;
+    use std::fmt::Display;
+
+    #[derive(Debug)]
+    pub struct Unknown;
+
+    impl Discipline for Unknown {
+        type Ascent = UnknownAscent;
+        type Score = UnknownScore;
+    }
+
+    pub struct UnknownAscent;
+
+    impl TryFrom<api::result::Ascent> for UnknownAscent {
+        type Error = MissingField;
+
+        fn try_from(_: api::result::Ascent) -> Result<Self, Self::Error> {
+            Ok(Self)
+        }
+    }
+
+    impl Display for UnknownAscent {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.write_str("?")
+        }
+    }
+
+    impl Ascent for UnknownAscent {
+        fn render(&self) -> LazyNodes {
+            rsx! { div { "?" } }
+        }
+    }
+
+    /// Every athlete has the same score, so the order of the API is kept
+    #[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+    pub struct UnknownScore;
+
+    impl Score for UnknownScore {
+        type Ascent = UnknownAscent;
+        const FIELDS: &'static [&'static str] = &[];
+        const COMPARABLE: bool = false;
+
+        fn render(&self) -> LazyNodes {
+            rsx! { "" }
+        }
+
+        fn calculate(_start_order: u64, _ascents: &[Option<Self::Ascent>]) -> Self {
+            Self
+        }
+
+        fn values(&self) -> Vec<String> {
+            Vec::new()
+        }
+
+        fn parse_official(_notation: &str) -> Option<Self> {
+            None
+        }
+
+        fn official(&self) -> String {
+            String::new()
+        }
     }
 }
@@ -0,0 +1,171 @@
+//! Exporting computed rankings to CSV and spreadsheet files
+use crate::api::result::{DisciplineTag, Results};
+use crate::discipline::{Boulder, Discipline, Lead, Score, Speed, Unknown};
+use crate::ranking;
+use crate::table::Table;
+
+/// The ranking as computed by us, sorted by rank, with a column per route
+pub fn results_table(results: &Results) -> Table {
+    match results.discipline {
+        DisciplineTag::Lead => ranking_table::<Lead>(results),
+        DisciplineTag::Boulder => ranking_table::<Boulder>(results),
+        DisciplineTag::Speed => ranking_table::<Speed>(results),
+        DisciplineTag::Unknown => ranking_table::<Unknown>(results),
+    }
+}
+
+fn ranking_table<D: Discipline>(results: &Results) -> Table {
+    let mut athletes = ranking::rank::<D>(results);
+    athletes.sort_by_key(|a| a.rank);
+
+    // Not every athlete has to have an ascent on every route yet, so we take
+    // the maximum to get all the routes.
+    let routes = athletes.iter().map(|a| a.ascents.len()).max().unwrap_or(0);
+    // Only paraclimbing rounds have sport classes
+    let sport_classes = athletes.iter().any(|a| a.sport_class.is_some());
+
+    let mut headers: Vec<String> = ["rank", "athlete_id", "first_name", "last_name", "country"]
+        .into_iter()
+        .map(String::from)
+        .collect();
+    if sport_classes {
+        headers.push("sport_class".into());
+    }
+    headers.extend((1..=routes).map(|i| format!("route_{i}")));
+    headers.extend(D::Score::FIELDS.iter().map(|f| f.to_string()));
+    let mut table = Table::new(headers);
+
+    for a in athletes {
+        let mut row = vec![
+            a.rank.to_string(),
+            a.id.to_string(),
+            a.first_name,
+            a.last_name,
+            a.country,
+        ];
+        if sport_classes {
+            row.push(a.sport_class.unwrap_or_default());
+        }
+        row.extend((0..routes).map(|i| {
+            a.ascents
+                .get(i)
+                .and_then(Option::as_ref)
+                .map(|x| x.to_string())
+                .unwrap_or_default()
+        }));
+        row.extend(a.score.values());
+        table.push(row);
+    }
+
+    table
+}
+
+/// A file name for the export of a round, without extension
+pub fn file_name(results: &Results) -> String {
+    let name = match &results.event {
+        Some(event) => format!("{event} - {} - {}", results.category, results.round),
+        None => format!("{} - {}", results.category, results.round),
+    };
+    safe_file_name(&name)
+}
+
+/// Replace the characters that can't be in a file name on every platform
+pub fn safe_file_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_alphanumeric() || " -_()".contains(c) {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Write the table to a file, the format is determined by the extension of
+/// the path: `csv`, `xlsx` or `ods`.
+#[cfg(feature = "desktop")]
+pub fn write(table: &Table, path: &std::path::Path) -> std::io::Result<()> {
+    use std::io::{Error, ErrorKind};
+
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("csv") => std::fs::write(path, table.to_csv()),
+        Some("xlsx") => {
+            write_xlsx(table, path).map_err(|e| Error::new(ErrorKind::Other, e.to_string()))
+        }
+        Some("ods") => {
+            write_ods(table, path).map_err(|e| Error::new(ErrorKind::Other, e.to_string()))
+        }
+        _ => Err(Error::new(
+            ErrorKind::InvalidInput,
+            "the file should have a csv, xlsx or ods extension",
+        )),
+    }
+}
+
+#[cfg(feature = "desktop")]
+fn write_xlsx(table: &Table, path: &std::path::Path) -> Result<(), rust_xlsxwriter::XlsxError> {
+    let mut workbook = rust_xlsxwriter::Workbook::new();
+    let sheet = workbook.add_worksheet();
+
+    for (col, header) in table.headers.iter().enumerate() {
+        sheet.write_string(0, col as u16, header)?;
+    }
+
+    for (row, cells) in table.rows.iter().enumerate() {
+        let row = row as u32 + 1;
+        for (col, cell) in cells.iter().enumerate() {
+            // Spreadsheets should be able to calculate with the scores
+            match cell.parse::<f64>() {
+                Ok(n) => sheet.write_number(row, col as u16, n)?,
+                Err(_) => sheet.write_string(row, col as u16, cell)?,
+            };
+        }
+    }
+
+    workbook.save(path)
+}
+
+#[cfg(feature = "desktop")]
+fn write_ods(table: &Table, path: &std::path::Path) -> Result<(), spreadsheet_ods::OdsError> {
+    let mut workbook = spreadsheet_ods::WorkBook::new_empty();
+    let mut sheet = spreadsheet_ods::Sheet::new("Ranking");
+
+    for (col, header) in table.headers.iter().enumerate() {
+        sheet.set_value(0, col as u32, header.as_str());
+    }
+
+    for (row, cells) in table.rows.iter().enumerate() {
+        let row = row as u32 + 1;
+        for (col, cell) in cells.iter().enumerate() {
+            match cell.parse::<f64>() {
+                Ok(n) => sheet.set_value(row, col as u32, n),
+                Err(_) => sheet.set_value(row, col as u32, cell.as_str()),
+            }
+        }
+    }
+
+    workbook.push_sheet(sheet);
+    spreadsheet_ods::write_ods(&mut workbook, path)
+}
+
+/// A `data:` URL with the CSV, so that the browser can download it
+#[cfg(feature = "web")]
+pub fn csv_data_url(table: &Table) -> String {
+    data_url("text/csv", &table.to_csv())
+}
+
+/// A `data:` URL with the text, so that the browser can download it
+#[cfg(feature = "web")]
+pub fn data_url(mime: &str, text: &str) -> String {
+    let encoded: String = text
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect();
+    format!("data:{mime};charset=utf-8,{encoded}")
+}
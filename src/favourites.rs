@@ -0,0 +1,128 @@
+//! Athletes and countries the user follows, which are highlighted everywhere
+use crate::api::{
+    self,
+    result::Results,
+    seasons::{Event, Season},
+};
+use crate::storage::{self, SaveError};
+use serde::{Deserialize, Serialize};
+
+const STORAGE_KEY: &str = "favourites";
+
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct Favourites {
+    pub athletes: Vec<FavouriteAthlete>,
+    /// Country codes, like `AUT`
+    pub countries: Vec<String>,
+}
+
+/// We keep the name of a favourite athlete, so that we can show them without
+/// fetching anything.
+#[derive(Clone, Serialize, Deserialize, PartialEq)]
+pub struct FavouriteAthlete {
+    pub athlete_id: u64,
+    pub firstname: String,
+    pub lastname: String,
+    pub country: String,
+}
+
+impl Favourites {
+    pub fn load() -> Self {
+        storage::load(STORAGE_KEY).unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<(), SaveError> {
+        storage::save(STORAGE_KEY, self)
+    }
+
+    pub fn is_athlete(&self, athlete_id: u64) -> bool {
+        self.athletes.iter().any(|a| a.athlete_id == athlete_id)
+    }
+
+    pub fn is_country(&self, country: &str) -> bool {
+        self.countries.iter().any(|c| c == country)
+    }
+
+    /// Whether the athlete is a favourite or from a favourite country
+    pub fn contains(&self, athlete_id: u64, country: &str) -> bool {
+        self.is_athlete(athlete_id) || self.is_country(country)
+    }
+
+    /// Add the athlete if they are not a favourite yet, otherwise remove them
+    pub fn toggle_athlete(&mut self, athlete: FavouriteAthlete) -> Result<(), SaveError> {
+        if self.is_athlete(athlete.athlete_id) {
+            self.athletes.retain(|a| a.athlete_id != athlete.athlete_id);
+        } else {
+            self.athletes.push(athlete);
+        }
+        self.save()
+    }
+
+    /// Add the country if it is not a favourite yet, otherwise remove it
+    pub fn toggle_country(&mut self, country: &str) -> Result<(), SaveError> {
+        if self.is_country(country) {
+            self.countries.retain(|c| c != country);
+        } else {
+            self.countries.push(country.to_string());
+        }
+        self.save()
+    }
+}
+
+/// A favourite athlete in a round of an event that is happening right now
+#[derive(Clone, PartialEq)]
+pub struct Appearance {
+    pub athlete: FavouriteAthlete,
+    pub event: String,
+    pub category: String,
+    pub round: String,
+    pub round_id: u64,
+    /// The official rank, if the athlete has one yet
+    pub rank: Option<u64>,
+}
+
+/// Find the favourite athletes in the rounds of the current events of a
+/// season.
+pub async fn today(favourites: &Favourites, season_id: u64) -> Option<Vec<Appearance>> {
+    let mut appearances = Vec::new();
+    if favourites.athletes.is_empty() {
+        return Some(appearances);
+    }
+
+    let season: Season = api::request(format!("seasons/{season_id}")).await?;
+    let now = chrono::offset::Utc::now();
+    for short_event in season
+        .events
+        .iter()
+        .filter(|e| e.starts_at < now && e.ends_at > now)
+    {
+        let Some(event) = api::request::<Event>(format!("events/{}", short_event.event_id)).await
+        else {
+            continue;
+        };
+        for dcat in &event.dcats {
+            for round in &dcat.category_rounds {
+                let url = format!("category_rounds/{}/results", round.category_round_id);
+                let Some(results) = api::request::<Results>(url).await else {
+                    continue;
+                };
+                for rank_athlete in &results.ranking {
+                    let id = rank_athlete.athlete.athlete_id;
+                    let Some(athlete) = favourites.athletes.iter().find(|a| a.athlete_id == id)
+                    else {
+                        continue;
+                    };
+                    appearances.push(Appearance {
+                        athlete: athlete.clone(),
+                        event: short_event.event.clone(),
+                        category: dcat.dcat_name.clone(),
+                        round: round.name.clone(),
+                        round_id: round.category_round_id,
+                        rank: rank_athlete.rank,
+                    });
+                }
+            }
+        }
+    }
+    Some(appearances)
+}
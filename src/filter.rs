@@ -0,0 +1,67 @@
+//! Showing only the events and categories of a discipline, league or category
+use crate::api::seasons::{ShortCategory, ShortEvent};
+use crate::storage::{self, SaveError};
+use serde::{Deserialize, Serialize};
+
+const STORAGE_KEY: &str = "filter";
+
+/// The disciplines that can be chosen in the filter
+pub const DISCIPLINES: [&str; 3] = ["boulder", "lead", "speed"];
+
+/// Every part of the filter is optional and compared without case. Events
+/// without the information that is filtered on are not filtered out, because
+/// older events don't always have it.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct Filter {
+    /// Like `boulder`
+    pub discipline: Option<String>,
+    /// Like `IFSC Youth`
+    pub league: Option<String>,
+    /// Like `Women`, which can only be checked on the categories of an event.
+    /// This is the text as it is typed, so it is trimmed when it is compared.
+    pub category: Option<String>,
+}
+
+impl Filter {
+    pub fn load() -> Self {
+        storage::load(STORAGE_KEY).unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<(), SaveError> {
+        storage::save(STORAGE_KEY, self)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.discipline.is_none()
+            && self.league.is_none()
+            && self
+                .category
+                .as_deref()
+                .map_or(true, |c| c.trim().is_empty())
+    }
+
+    pub fn matches_event(&self, event: &ShortEvent) -> bool {
+        let league = event.league_name.is_empty() || matches(&self.league, &event.league_name);
+        let discipline = event.disciplines.is_empty()
+            || event
+                .disciplines
+                .iter()
+                .any(|d| matches(&self.discipline, &d.kind));
+        league && discipline
+    }
+
+    pub fn matches_category(&self, category: &ShortCategory) -> bool {
+        let discipline = category.discipline_kind.is_empty()
+            || matches(&self.discipline, &category.discipline_kind);
+        let name =
+            category.category_name.is_empty() || matches(&self.category, &category.category_name);
+        discipline && name
+    }
+}
+
+fn matches(filter: &Option<String>, value: &str) -> bool {
+    filter
+        .as_deref()
+        .map(str::trim)
+        .map_or(true, |f| f.is_empty() || f.eq_ignore_ascii_case(value))
+}
@@ -0,0 +1,310 @@
+//! Comparing two athletes over every round in which both of them climbed
+use crate::api::{
+    self,
+    athlete::AthleteProfile,
+    result::{DisciplineTag, Results},
+    seasons::{Event, ShortCategory},
+};
+use crate::search;
+use crate::standings::fetch_rounds;
+use crate::table::Table;
+use crate::{leaderboard::LeaderboardInput, Page};
+use dioxus::prelude::*;
+use std::cmp::Ordering;
+
+/// A round in which both athletes have an official rank
+#[derive(Clone, Debug, PartialEq)]
+pub struct Meeting {
+    pub event: String,
+    pub category: String,
+    pub round: String,
+    pub round_id: u64,
+    pub discipline: DisciplineTag,
+    /// The ranks of the first and the second athlete
+    pub ranks: (u64, u64),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Winner {
+    First,
+    Second,
+    Tie,
+}
+
+impl Meeting {
+    /// The athlete that finished higher
+    pub fn winner(&self) -> Winner {
+        match self.ranks.0.cmp(&self.ranks.1) {
+            Ordering::Less => Winner::First,
+            Ordering::Greater => Winner::Second,
+            Ordering::Equal => Winner::Tie,
+        }
+    }
+}
+
+/// How often each athlete finished higher in a discipline
+#[derive(Clone, Debug, PartialEq)]
+pub struct Record {
+    pub discipline: DisciplineTag,
+    pub first: usize,
+    pub second: usize,
+    pub ties: usize,
+}
+
+/// The meeting of the athletes in a round, if both have a rank in it
+pub fn meeting(
+    event: &str,
+    round_id: u64,
+    results: &Results,
+    first: u64,
+    second: u64,
+) -> Option<Meeting> {
+    let rank = |id| {
+        results
+            .ranking
+            .iter()
+            .find(|a| a.athlete.athlete_id == id)?
+            .rank
+    };
+    Some(Meeting {
+        event: event.to_string(),
+        category: results.category.clone(),
+        round: results.round.clone(),
+        round_id,
+        discipline: results.discipline.clone(),
+        ranks: (rank(first)?, rank(second)?),
+    })
+}
+
+/// The record of every discipline, in the order the disciplines first appear
+pub fn records(meetings: &[Meeting]) -> Vec<Record> {
+    let mut records: Vec<Record> = Vec::new();
+    for meeting in meetings {
+        let index = match records
+            .iter()
+            .position(|r| r.discipline == meeting.discipline)
+        {
+            Some(index) => index,
+            None => {
+                records.push(Record {
+                    discipline: meeting.discipline.clone(),
+                    first: 0,
+                    second: 0,
+                    ties: 0,
+                });
+                records.len() - 1
+            }
+        };
+        let record = &mut records[index];
+        match meeting.winner() {
+            Winner::First => record.first += 1,
+            Winner::Second => record.second += 1,
+            Winner::Tie => record.ties += 1,
+        }
+    }
+    records
+}
+
+#[derive(Clone, PartialEq)]
+pub struct Comparison {
+    pub first: AthleteProfile,
+    pub second: AthleteProfile,
+    pub meetings: Vec<Meeting>,
+}
+
+/// The events in which both athletes competed
+pub fn shared_events(first: &AthleteProfile, second: &AthleteProfile) -> Vec<u64> {
+    let mut events: Vec<u64> = Vec::new();
+    for result in &first.all_results {
+        let shared = second
+            .all_results
+            .iter()
+            .any(|r| r.event_id == result.event_id);
+        if shared && !events.contains(&result.event_id) {
+            events.push(result.event_id);
+        }
+    }
+    events
+}
+
+/// Whether the athlete climbed in a category of an event
+fn climbed_in(athlete: &AthleteProfile, event_id: u64, category: &ShortCategory) -> bool {
+    athlete
+        .all_results
+        .iter()
+        .any(|r| r.event_id == event_id && r.is_in(&category.dcat_name))
+}
+
+/// Find the events in which both athletes competed and compare them in every
+/// round of the categories both of them climbed in.
+pub async fn compare(first: u64, second: u64) -> Option<Comparison> {
+    let first: AthleteProfile = api::request(format!("athletes/{first}")).await?;
+    let second: AthleteProfile = api::request(format!("athletes/{second}")).await?;
+
+    let mut meetings = Vec::new();
+    for id in shared_events(&first, &second) {
+        let Some(event) = api::request::<Event>(format!("events/{id}")).await else {
+            continue;
+        };
+        // Only the rounds of the shared categories are fetched, a long rivalry
+        // would need far too many requests otherwise
+        let categories = event
+            .dcats
+            .iter()
+            .filter(|c| climbed_in(&first, id, c) && climbed_in(&second, id, c));
+        for category in categories {
+            for (round, results) in fetch_rounds(category).await {
+                let Some(results) = results else {
+                    continue;
+                };
+                meetings.extend(meeting(
+                    &event.name,
+                    round.category_round_id,
+                    &results,
+                    first.id,
+                    second.id,
+                ));
+            }
+        }
+    }
+
+    Some(Comparison {
+        first,
+        second,
+        meetings,
+    })
+}
+
+/// Every meeting with the ranks of both athletes
+pub fn table(comparison: &Comparison) -> Table {
+    let mut table = Table::new([
+        "event".to_string(),
+        "category".to_string(),
+        "round".to_string(),
+        comparison.first.lastname.clone(),
+        comparison.second.lastname.clone(),
+    ]);
+    for m in &comparison.meetings {
+        table.push(vec![
+            m.event.clone(),
+            m.category.clone(),
+            m.round.clone(),
+            m.ranks.0.to_string(),
+            m.ranks.1.to_string(),
+        ]);
+    }
+    table
+}
+
+pub fn HeadToHead(cx: Scope) -> Element {
+    let first = use_state(cx, || None::<u64>);
+    let second = use_state(cx, || None::<u64>);
+    let compared = use_state(cx, || None::<(u64, u64)>);
+    let page = use_shared_state::<Page>(cx).unwrap();
+    let future = use_future(cx, (*compared.get(),), |(compared,)| async move {
+        let (first, second) = compared?;
+        compare(first, second).await
+    });
+
+    // The athletes from the results we have seen can be picked by name
+    let athletes = search::seen_athletes().into_iter().map(|a| {
+        rsx! { option { value: "{a.athlete_id}", "{a.firstname} {a.lastname} ({a.country})" } }
+    });
+
+    let comparison = match (compared.get(), future.value()) {
+        (None, _) => rsx! { "" },
+        (Some(_), Some(Some(c))) => {
+            let name = |a: &AthleteProfile| format!("{} {}", a.firstname, a.lastname);
+            let (first_name, second_name) = (name(&c.first), name(&c.second));
+            let records = records(&c.meetings).into_iter().map(|r| {
+                rsx! {
+                    tr {
+                        td { "{r.discipline}" }
+                        td { class: "total", "{r.first}" }
+                        td { class: "total", "{r.second}" }
+                        td { "{r.ties}" }
+                    }
+                }
+            });
+            let meetings = c.meetings.iter().map(|m| {
+                let (first_class, second_class) = match m.winner() {
+                    Winner::First => ("total", ""),
+                    Winner::Second => ("", "total"),
+                    Winner::Tie => ("", ""),
+                };
+                let (first_rank, second_rank) = m.ranks;
+                let event_name = m.event.clone();
+                let round_id = m.round_id;
+                rsx! {
+                    tr {
+                        class: "round-link",
+                        onclick: move |_| *page.write() = Page::Leaderboard(LeaderboardInput::Api(event_name.clone(), round_id)),
+                        td { "{m.event}" }
+                        td { "{m.category} {m.round}" }
+                        td { class: "{first_class}", "{first_rank}" }
+                        td { class: "{second_class}", "{second_rank}" }
+                    }
+                }
+            });
+            rsx! {
+                h2 { "{first_name} ({c.first.country}) vs {second_name} ({c.second.country})" }
+                if c.meetings.is_empty() {
+                    rsx! { div { class: "hint", "They have not climbed in the same round" } }
+                }
+                table {
+                    class: "standings",
+                    thead {
+                        tr {
+                            th { "Discipline" }
+                            th { "{first_name}" }
+                            th { "{second_name}" }
+                            th { "Ties" }
+                        }
+                    }
+                    tbody { records }
+                }
+                table {
+                    class: "standings",
+                    thead {
+                        tr {
+                            th { "Event" }
+                            th { "Round" }
+                            th { "{first_name}" }
+                            th { "{second_name}" }
+                        }
+                    }
+                    tbody { meetings }
+                }
+            }
+        }
+        (Some(_), Some(None)) => rsx! { "Could not load the athletes" },
+        (Some(_), None) => rsx! { "Loading..." },
+    };
+
+    cx.render(rsx! {
+        h1 { "Head to head" }
+        div {
+            class: "form",
+            input {
+                list: "athletes",
+                placeholder: "Athlete id",
+                oninput: move |e| first.set(e.value.trim().parse().ok()),
+            }
+            input {
+                list: "athletes",
+                placeholder: "Athlete id",
+                oninput: move |e| second.set(e.value.trim().parse().ok()),
+            }
+            button {
+                onclick: move |_| {
+                    if let (Some(a), Some(b)) = (first.get(), second.get()) {
+                        compared.set(Some((*a, *b)));
+                    }
+                },
+                "Compare"
+            }
+            datalist { id: "athletes", athletes }
+        }
+        comparison
+    })
+}
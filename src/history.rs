@@ -0,0 +1,499 @@
+//! A local database with the results of past seasons, so that the histories
+//! of athletes, statistics and rankings can be computed without fetching
+//! every round again.
+//!
+//! The database is filled with [`History::sync_season`] and only contains
+//! what is needed for the rankings: the official rank and score of every
+//! athlete in every round, not the individual ascents.
+use crate::api::{
+    self,
+    result::{Athlete, Country, DisciplineTag, RankAthlete, Results},
+    seasons::{Event, Season, ShortEvent},
+};
+use crate::rating::Ratings;
+use crate::standings::{self, EventResult};
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::path::Path;
+
+/// The database in the working directory, next to the `cache` and `export`
+/// directories
+pub const DEFAULT_PATH: &str = "history.sqlite";
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS seasons (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS events (
+    id INTEGER PRIMARY KEY,
+    season_id INTEGER NOT NULL REFERENCES seasons(id),
+    name TEXT NOT NULL,
+    location TEXT NOT NULL,
+    league TEXT NOT NULL,
+    starts_at TEXT NOT NULL,
+    ends_at TEXT NOT NULL,
+    -- Whether the event was finished and all its rounds were stored
+    complete INTEGER NOT NULL DEFAULT 0
+);
+CREATE TABLE IF NOT EXISTS rounds (
+    id INTEGER PRIMARY KEY,
+    event_id INTEGER NOT NULL REFERENCES events(id),
+    -- Like 'BOULDER Women'
+    category TEXT NOT NULL,
+    discipline TEXT NOT NULL,
+    name TEXT NOT NULL,
+    -- The order of the round in its category
+    position INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS athletes (
+    id INTEGER PRIMARY KEY,
+    firstname TEXT NOT NULL,
+    lastname TEXT NOT NULL,
+    country TEXT NOT NULL,
+    flag_url TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS results (
+    round_id INTEGER NOT NULL REFERENCES rounds(id),
+    athlete_id INTEGER NOT NULL REFERENCES athletes(id),
+    rank INTEGER,
+    score TEXT,
+    starting_group TEXT,
+    group_rank INTEGER,
+    PRIMARY KEY (round_id, athlete_id)
+);
+";
+
+pub struct History {
+    connection: Connection,
+}
+
+/// What was stored by a sync
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Synced {
+    pub events: usize,
+    pub rounds: usize,
+    /// The events that could not be loaded from the API
+    pub failed_events: usize,
+}
+
+/// A round an athlete climbed in
+#[derive(Clone, Debug, PartialEq)]
+pub struct HistoryEntry {
+    pub season: String,
+    pub event: String,
+    pub starts_at: DateTime<Utc>,
+    pub category: String,
+    pub discipline: DisciplineTag,
+    pub round: String,
+    pub round_id: u64,
+    pub rank: Option<u64>,
+}
+
+/// The numbers of an athlete in a discipline
+#[derive(Clone, Debug, PartialEq)]
+pub struct Statistics {
+    pub discipline: DisciplineTag,
+    pub rounds: usize,
+    pub finals: usize,
+    /// Finals won
+    pub wins: usize,
+    /// Top 3 places in finals
+    pub podiums: usize,
+    pub best_rank: Option<u64>,
+}
+
+impl History {
+    pub fn open(path: &Path) -> rusqlite::Result<Self> {
+        Self::init(Connection::open(path)?)
+    }
+
+    #[cfg(test)]
+    pub fn open_in_memory() -> rusqlite::Result<Self> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(connection: Connection) -> rusqlite::Result<Self> {
+        connection.execute_batch(SCHEMA)?;
+        Ok(Self { connection })
+    }
+
+    pub fn insert_season(&self, id: u64, name: &str) -> rusqlite::Result<()> {
+        self.connection.execute(
+            "INSERT OR REPLACE INTO seasons (id, name) VALUES (?1, ?2)",
+            params![id, name],
+        )?;
+        Ok(())
+    }
+
+    pub fn insert_event(
+        &self,
+        season_id: u64,
+        event: &ShortEvent,
+        complete: bool,
+    ) -> rusqlite::Result<()> {
+        self.connection.execute(
+            "INSERT OR REPLACE INTO events
+                (id, season_id, name, location, league, starts_at, ends_at, complete)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                event.event_id,
+                season_id,
+                event.event,
+                event.location,
+                event.league_name,
+                event.starts_at.to_rfc3339(),
+                event.ends_at.to_rfc3339(),
+                complete,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Store the results of a round, replacing what was stored for it before
+    pub fn insert_round(
+        &self,
+        event_id: u64,
+        category: &str,
+        position: usize,
+        round_id: u64,
+        results: &Results,
+    ) -> rusqlite::Result<()> {
+        let transaction = self.connection.unchecked_transaction()?;
+        transaction.execute(
+            "INSERT OR REPLACE INTO rounds (id, event_id, category, discipline, name, position)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                round_id,
+                event_id,
+                category,
+                stored_discipline(&results.discipline),
+                results.round,
+                position,
+            ],
+        )?;
+        transaction.execute("DELETE FROM results WHERE round_id = ?1", params![round_id])?;
+        for a in &results.ranking {
+            transaction.execute(
+                "INSERT OR REPLACE INTO athletes (id, firstname, lastname, country, flag_url)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    a.athlete.athlete_id,
+                    a.athlete.firstname,
+                    a.athlete.lastname,
+                    a.athlete.country.country,
+                    a.athlete.country.flag_url,
+                ],
+            )?;
+            transaction.execute(
+                "INSERT INTO results (round_id, athlete_id, rank, score, starting_group, group_rank)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    round_id,
+                    a.athlete.athlete_id,
+                    a.rank,
+                    a.score,
+                    a.starting_group,
+                    a.group_rank,
+                ],
+            )?;
+        }
+        transaction.commit()
+    }
+
+    /// Whether the event is finished and all its rounds are stored, so it
+    /// doesn't have to be fetched again
+    pub fn is_complete(&self, event_id: u64) -> rusqlite::Result<bool> {
+        let complete = self
+            .connection
+            .query_row(
+                "SELECT complete FROM events WHERE id = ?1",
+                params![event_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(complete.unwrap_or(false))
+    }
+
+    /// Fetch a season with all its events and rounds from the API and store
+    /// them. Events that were completely stored before are skipped, events
+    /// that could not be loaded are counted. Returns `None` if the season
+    /// could not be loaded.
+    pub async fn sync_season(&self, season_id: u64) -> rusqlite::Result<Option<Synced>> {
+        let Some(season) = api::request::<Season>(format!("seasons/{season_id}")).await else {
+            return Ok(None);
+        };
+        self.insert_season(season_id, &season.name)?;
+
+        let now = chrono::offset::Utc::now();
+        let mut synced = Synced::default();
+        for short_event in &season.events {
+            if self.is_complete(short_event.event_id)? {
+                continue;
+            }
+            let url = format!("events/{}", short_event.event_id);
+            let Some(event) = api::request::<Event>(url).await else {
+                synced.failed_events += 1;
+                continue;
+            };
+
+            let mut complete = short_event.ends_at < now;
+            self.insert_event(season_id, short_event, false)?;
+            for category in &event.dcats {
+                for (position, round) in category.category_rounds.iter().enumerate() {
+                    let url = format!("category_rounds/{}/results", round.category_round_id);
+                    let Some(results) = api::request::<Results>(url).await else {
+                        complete = false;
+                        continue;
+                    };
+                    self.insert_round(
+                        event.id,
+                        &category.dcat_name,
+                        position,
+                        round.category_round_id,
+                        &results,
+                    )?;
+                    synced.rounds += 1;
+                }
+            }
+            self.insert_event(season_id, short_event, complete)?;
+            synced.events += 1;
+        }
+        Ok(Some(synced))
+    }
+
+    /// Every round the athlete climbed in, from the oldest to the newest
+    pub fn athlete_history(&self, athlete_id: u64) -> rusqlite::Result<Vec<HistoryEntry>> {
+        let mut statement = self.connection.prepare(
+            "SELECT seasons.name, events.name, events.starts_at, rounds.category,
+                    rounds.discipline, rounds.name, rounds.id, results.rank
+             FROM results
+             JOIN rounds ON rounds.id = results.round_id
+             JOIN events ON events.id = rounds.event_id
+             JOIN seasons ON seasons.id = events.season_id
+             WHERE results.athlete_id = ?1
+             ORDER BY events.starts_at, rounds.category, rounds.position",
+        )?;
+        let rows = statement.query_map(params![athlete_id], |row| {
+            Ok(HistoryEntry {
+                season: row.get(0)?,
+                event: row.get(1)?,
+                starts_at: time(2, &row.get::<_, String>(2)?)?,
+                category: row.get(3)?,
+                discipline: discipline(&row.get::<_, String>(4)?),
+                round: row.get(5)?,
+                round_id: row.get(6)?,
+                rank: row.get(7)?,
+            })
+        })?;
+        rows.collect()
+    }
+
+    /// The stored rounds of a category at an event, in the order they were
+    /// climbed. Only the official ranks and scores are in the results, in the
+    /// order of the API.
+    pub fn rounds(&self, event_id: u64, category: &str) -> rusqlite::Result<Vec<Results>> {
+        let mut statement = self.connection.prepare(
+            "SELECT rounds.id, rounds.discipline, rounds.name, events.name
+             FROM rounds JOIN events ON events.id = rounds.event_id
+             WHERE rounds.event_id = ?1 AND rounds.category = ?2
+             ORDER BY rounds.position",
+        )?;
+        let rounds = statement
+            .query_map(params![event_id, category], |row| {
+                Ok((
+                    row.get::<_, u64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let mut statement = self.connection.prepare(
+            "SELECT athletes.id, athletes.firstname, athletes.lastname, athletes.country,
+                    athletes.flag_url, results.rank, results.score, results.starting_group,
+                    results.group_rank
+             FROM results JOIN athletes ON athletes.id = results.athlete_id
+             WHERE results.round_id = ?1
+             ORDER BY results.rowid",
+        )?;
+        rounds
+            .into_iter()
+            .map(|(id, tag, round, event)| {
+                let ranking = statement
+                    .query_map(params![id], |row| {
+                        Ok(RankAthlete {
+                            athlete: Athlete {
+                                athlete_id: row.get(0)?,
+                                firstname: row.get(1)?,
+                                lastname: row.get(2)?,
+                                country: Country {
+                                    country: row.get(3)?,
+                                    flag_url: row.get(4)?,
+                                },
+                            },
+                            rank: row.get(5)?,
+                            score: row.get(6)?,
+                            starting_group: row.get(7)?,
+                            group_rank: row.get(8)?,
+                            paraclimbing_sport_class: None,
+                            sport_class_state: None,
+                            route_start_orders: Vec::new(),
+                            ascents: Vec::new(),
+                            active: false,
+                        })
+                    })?
+                    .collect::<rusqlite::Result<Vec<_>>>()?;
+                Ok(Results {
+                    discipline: discipline(&tag),
+                    event: Some(event),
+                    category: category.to_string(),
+                    round,
+                    ranking,
+                })
+            })
+            .collect()
+    }
+
+    /// The final results of every finished World Cup of a season by category,
+    /// like [`standings::fetch_season`] but from the database
+    pub fn season_categories(&self, season_id: u64) -> rusqlite::Result<Vec<standings::Category>> {
+        let now = chrono::offset::Utc::now().to_rfc3339();
+        let mut statement = self.connection.prepare(
+            "SELECT DISTINCT events.id, events.name, events.league, rounds.category
+             FROM events JOIN rounds ON rounds.event_id = events.id
+             WHERE events.season_id = ?1 AND events.ends_at < ?2
+             ORDER BY events.starts_at, events.id, rounds.category",
+        )?;
+        let event_categories = statement
+            .query_map(params![season_id, now], |row| {
+                Ok((
+                    row.get::<_, u64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let mut categories: Vec<standings::Category> = Vec::new();
+        for (event_id, event, league, category) in event_categories {
+            if !standings::is_world_cup(&league, &event) {
+                continue;
+            }
+            let placings = standings::event_ranking(&self.rounds(event_id, &category)?);
+            if placings.is_empty() {
+                continue;
+            }
+            let result = EventResult {
+                event_id,
+                event,
+                placings,
+            };
+            match categories.iter_mut().find(|c| c.name == category) {
+                Some(c) => c.events.push(result),
+                None => categories.push(standings::Category {
+                    name: category,
+                    events: vec![result],
+                }),
+            }
+        }
+        Ok(categories)
+    }
+
+    /// The ratings after every stored round, in the order of the events
+    pub fn ratings(&self) -> rusqlite::Result<Ratings> {
+        let mut statement = self.connection.prepare(
+            "SELECT DISTINCT events.id, events.starts_at, rounds.category
+             FROM events JOIN rounds ON rounds.event_id = events.id
+             ORDER BY events.starts_at, events.id, rounds.category",
+        )?;
+        let event_categories = statement
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, u64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let mut ratings = Ratings::default();
+        for (event_id, starts_at, category) in event_categories {
+            let at = time(1, &starts_at)?;
+            for results in self.rounds(event_id, &category)? {
+                ratings.add_round(at, &results);
+            }
+        }
+        Ok(ratings)
+    }
+}
+
+/// The statistics of every discipline in the history of an athlete, in the
+/// order the disciplines first appear
+pub fn statistics(history: &[HistoryEntry]) -> Vec<Statistics> {
+    let mut statistics: Vec<Statistics> = Vec::new();
+    for entry in history {
+        let index = match statistics
+            .iter()
+            .position(|s| s.discipline == entry.discipline)
+        {
+            Some(index) => index,
+            None => {
+                statistics.push(Statistics {
+                    discipline: entry.discipline.clone(),
+                    rounds: 0,
+                    finals: 0,
+                    wins: 0,
+                    podiums: 0,
+                    best_rank: None,
+                });
+                statistics.len() - 1
+            }
+        };
+        let s = &mut statistics[index];
+        s.rounds += 1;
+        if let Some(rank) = entry.rank {
+            s.best_rank = Some(s.best_rank.map_or(rank, |best| best.min(rank)));
+        }
+        if entry.round.eq_ignore_ascii_case("final") {
+            s.finals += 1;
+            if entry.rank == Some(1) {
+                s.wins += 1;
+            }
+            if entry.rank.is_some_and(|r| r <= 3) {
+                s.podiums += 1;
+            }
+        }
+    }
+    statistics
+}
+
+/// A time as it is stored, in RFC 3339. A row with another time is an error
+/// rather than the default time, so that it doesn't end up in the wrong place.
+fn time(column: usize, text: &str) -> rusqlite::Result<DateTime<Utc>> {
+    text.parse().map_err(|e| {
+        rusqlite::Error::FromSqlConversionFailure(column, rusqlite::types::Type::Text, Box::new(e))
+    })
+}
+
+/// The discipline as it is stored, which is its name in the API
+fn stored_discipline(tag: &DisciplineTag) -> &'static str {
+    match tag {
+        DisciplineTag::Lead => "Lead",
+        DisciplineTag::Boulder => "Boulder",
+        DisciplineTag::Speed => "Speed",
+        DisciplineTag::Unknown => "Unknown",
+    }
+}
+
+/// The discipline from how it is stored, see [`stored_discipline`]
+fn discipline(tag: &str) -> DisciplineTag {
+    match tag {
+        "Lead" => DisciplineTag::Lead,
+        "Boulder" => DisciplineTag::Boulder,
+        "Speed" => DisciplineTag::Speed,
+        _ => DisciplineTag::Unknown,
+    }
+}
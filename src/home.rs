@@ -1,14 +1,20 @@
 use crate::api::seasons::{
-    Event as ApiEvent, Season, SeasonsResponse,
+    Event as ApiEvent, RoundStatus, Season, SeasonsResponse,
     ShortEvent, ShortSeason,
 };
-use crate::leaderboard::LeaderboardInput;
+use crate::favourites::{self, Favourites};
+use crate::filter::{Filter, DISCIPLINES};
+use crate::leaderboard::{sleep, LeaderboardInput};
+use crate::standings::CountryScope;
+use crate::storage::SaveError;
 use crate::{api, Page};
 use chrono::{DateTime, Local};
+use std::time::Duration;
 use dioxus::prelude::*;
 
 fn Season(cx: Scope<ShortSeason>) -> Element {
     let expanded = use_state(cx, || false);
+    let page = use_shared_state::<Page>(cx).unwrap();
 
     let expanded_class = if *expanded.get() {
         "expanded"
@@ -23,7 +29,27 @@ fn Season(cx: Scope<ShortSeason>) -> Element {
                 onclick: move |_| expanded.modify(|b| !b), "{cx.props.name}"
             }
             if *expanded.get() {
-                rsx!{ EventList { ..cx.props.clone() } }
+                rsx!{
+                    div {
+                        class: "nested",
+                        div {
+                            class: "event",
+                            onclick: move |_| *page.write() = Page::Standings(cx.props.clone()),
+                            "World Cup ranking"
+                        }
+                        div {
+                            class: "event",
+                            onclick: move |_| *page.write() = Page::Calendar(cx.props.clone()),
+                            "Calendar"
+                        }
+                        div {
+                            class: "event",
+                            onclick: move |_| *page.write() = Page::Countries(CountryScope::Season(cx.props.clone())),
+                            "Medals and national teams"
+                        }
+                    }
+                    EventList { ..cx.props.clone() }
+                }
             }
         }
     })
@@ -32,10 +58,12 @@ fn Season(cx: Scope<ShortSeason>) -> Element {
 fn EventList(cx: Scope<ShortSeason>) -> Element {
     let url = format!("seasons/{}", cx.props.id);
     let future = use_future(cx, (&cx.props.id,), |_| api::request::<Season>(url));
+    let filter = use_shared_state::<Filter>(cx).unwrap();
 
     let events = match future.value() {
         Some(Some(season)) => {
-            let mut events = season.events.clone();
+            let filter = filter.read();
+            let mut events: Vec<_> = season.events.iter().filter(|e| filter.matches_event(e)).cloned().collect();
             events.sort_by_key(|e| e.starts_at);
             rsx! { events.into_iter().map(|e| rsx!{ Event { ..e } })}
         }
@@ -67,6 +95,48 @@ fn Event(cx: Scope<ShortEvent>) -> Element {
         "Finished"
     };
 
+    // Only the events that are happening can have a live round, so the other
+    // events don't have to be loaded. The live round changes during the event,
+    // so it is loaded again every minute until the event ends.
+    let current = state == "Started";
+    let live = use_state(cx, || None::<(String, u64)>);
+    use_future(cx, (&cx.props.event_id, &current), |(id, current)| {
+        let live = live.to_owned();
+        let ends_at = cx.props.ends_at;
+        async move {
+            while current && chrono::offset::Utc::now() < ends_at {
+                let now = chrono::offset::Utc::now();
+                let round = api::request::<ApiEvent>(format!("events/{id}"))
+                    .await
+                    .and_then(|event| {
+                        let (category, round) = event.live_round(now)?;
+                        let name = format!("{} {}", category.dcat_name, round.name);
+                        Some((name, round.category_round_id))
+                    });
+                if *live.current() != round {
+                    live.set(round);
+                }
+                sleep(Duration::from_secs(60)).await;
+            }
+            live.set(None);
+        }
+    });
+    let page = use_shared_state::<Page>(cx).unwrap();
+    let jump = match live.get() {
+        Some((name, round_id)) => {
+            let event_name = cx.props.event.clone();
+            let round_id = *round_id;
+            rsx! {
+                div {
+                    class: "event round-live",
+                    onclick: move |_| *page.write() = Page::Leaderboard(LeaderboardInput::Api(event_name.clone(), round_id)),
+                    "Jump to live round: {name}"
+                }
+            }
+        }
+        None => rsx! { "" },
+    };
+
     let starts_at: DateTime<Local> = cx.props.starts_at.into();
     let ends_at: DateTime<Local> = cx.props.ends_at.into();
 
@@ -85,6 +155,7 @@ fn Event(cx: Scope<ShortEvent>) -> Element {
                     "{state} | {date} | {start_time} - {end_time}"
                 }
             }
+            jump
             if *expanded.get() {
                 rsx!{ CategoryList { ..cx.props.clone() } }
             }
@@ -92,42 +163,85 @@ fn Event(cx: Scope<ShortEvent>) -> Element {
     })
 }
 
+/// A single season, as opened from the search
+pub fn SeasonPage(cx: Scope<ShortSeason>) -> Element {
+    cx.render(rsx! {
+        h1 { "{cx.props.name}" }
+        EventList { ..cx.props.clone() }
+    })
+}
+
+/// A single event, as opened from the search
+pub fn EventPage(cx: Scope<ShortEvent>) -> Element {
+    cx.render(rsx! {
+        h1 { "{cx.props.event}" }
+        CategoryList { ..cx.props.clone() }
+    })
+}
+
 fn CategoryList(cx: Scope<ShortEvent>) -> Element {
     let url = format!("events/{}", cx.props.event_id);
     let future = use_future(cx, (&cx.props.event_id,), |_| api::request::<ApiEvent>(url));
     let page = use_shared_state::<Page>(cx).unwrap();
+    let filter = use_shared_state::<Filter>(cx).unwrap();
 
-    let categories = match future.value() {
-        Some(Some(event)) => event.dcats.clone(),
+    let now = chrono::offset::Utc::now();
+
+    let categories: Vec<_> = match future.value() {
+        Some(Some(event)) => event.dcats.iter().filter(|c| filter.read().matches_category(c)).cloned().collect(),
         _ => return cx.render(rsx! { "Loading..." }),
     };
 
-    let nodes = categories.into_iter().map(|c| rsx!{ 
-        div {
-            div { class: "event", "{c.dcat_name}" },
+    let nodes = categories.into_iter().map(|c| {
+        let category = c.clone();
+        rsx! {
             div {
-                class: "nested",
-                c.category_rounds.iter().map(|r| {
-                    let event_name = cx.props.event.clone();
-                    let round_id = r.category_round_id;
-                    let f = move |_| {
-                        *page.write() = Page::Leaderboard(LeaderboardInput::Api(event_name.clone(),  round_id));
-                    };
-                    rsx! {
-                        div {
-                            onclick: f,
-                            class: "event",
-                            "{r.name}"
+                div {
+                    class: "event",
+                    onclick: move |_| *page.write() = Page::EventCategory(cx.props.clone(), category.clone()),
+                    div { "{c.dcat_name}" }
+                    div { class: "datetime", "{c.status}" }
+                },
+                div {
+                    class: "nested",
+                    c.category_rounds.iter().map(|r| {
+                        let event_name = cx.props.event.clone();
+                        let round_id = r.category_round_id;
+                        let f = move |_| {
+                            *page.write() = Page::Leaderboard(LeaderboardInput::Api(event_name.clone(),  round_id));
+                        };
+                        let status = r.round_status(cx.props.ends_at, now);
+                        let live_class = if status == RoundStatus::Live { "round-live" } else { "" };
+                        let start = r.starts_at.map(|t| {
+                            let t: DateTime<Local> = t.into();
+                            format!(" | {}", t.format("%b %e %H:%M"))
+                        }).unwrap_or_default();
+                        rsx! {
+                            div {
+                                onclick: f,
+                                class: "event {live_class}",
+                                div { "{r.name}" }
+                                div { class: "datetime", "{status}{start}" }
+                            }
                         }
-                    }
-                })
+                    })
+                }
             }
         }
     });
     
+    let countries = rsx! {
+        div {
+            class: "event",
+            onclick: move |_| *page.write() = Page::Countries(CountryScope::Event(cx.props.clone())),
+            "Medals and national teams"
+        }
+    };
+
     cx.render(rsx! {
         div {
             class: "nested",
+            countries
             nodes
         }
     })
@@ -135,11 +249,38 @@ fn CategoryList(cx: Scope<ShortEvent>) -> Element {
 
 pub fn Home(cx: Scope) -> Element {
     let future = use_future(cx, (), |_| api::request::<SeasonsResponse>(String::new()));
+    let page = use_shared_state::<Page>(cx).unwrap();
+
+    let local = rsx! {
+        div {
+            class: "season",
+            onclick: move |_| *page.write() = Page::Search,
+            "Search"
+        }
+        div {
+            class: "season",
+            onclick: move |_| *page.write() = Page::HeadToHead,
+            "Head to head"
+        }
+        div {
+            class: "season",
+            onclick: move |_| *page.write() = Page::PredictionGame,
+            "Prediction game"
+        }
+        div {
+            class: "season",
+            onclick: move |_| *page.write() = Page::LocalCompetitions,
+            "Local competitions"
+        }
+    };
 
     cx.render(match future.value() {
         Some(Some(SeasonsResponse { seasons })) => {
             rsx! {
-                RelevantEvents { id: seasons[0].id, name: seasons[0].name.clone() }
+                local
+                YourAthletes { ..seasons[0].clone() }
+                FilterBar { ..seasons[0].clone() }
+                RelevantEvents { ..seasons[0].clone() }
                 h1 { "All seasons" }
                 div {
                     class: "seasons-table",
@@ -147,13 +288,122 @@ pub fn Home(cx: Scope) -> Element {
                 }
             }
         }
-        _ => rsx! { "Loading..." },
+        _ => rsx! { local "Loading..." },
+    })
+}
+
+/// The rounds of the current events in which favourite athletes compete
+pub fn YourAthletes(cx: Scope<ShortSeason>) -> Element {
+    let favourites = use_shared_state::<Favourites>(cx).unwrap();
+    let page = use_shared_state::<Page>(cx).unwrap();
+    let current = favourites.read().clone();
+    let future = use_future(cx, (&cx.props.id, &current), |(id, favourites)| async move {
+        favourites::today(&favourites, id).await
+    });
+
+    if current.athletes.is_empty() {
+        return cx.render(rsx! {
+            h1 { "Your athletes today" }
+            div { class: "hint", "Follow athletes with the ☆ on a leaderboard to see them here" }
+        });
+    }
+
+    let appearances = match future.value() {
+        Some(Some(appearances)) if appearances.is_empty() => rsx! { "None of your athletes are climbing today" },
+        Some(Some(appearances)) => rsx! {
+            appearances.iter().map(|a| {
+                let event = a.event.clone();
+                let round_id = a.round_id;
+                let rank = a.rank.map(|r| format!(" | Rank {r}")).unwrap_or_default();
+                rsx! {
+                    div {
+                        class: "event",
+                        onclick: move |_| *page.write() = Page::Leaderboard(LeaderboardInput::Api(event.clone(), round_id)),
+                        div { "{a.athlete.firstname} {a.athlete.lastname} ({a.athlete.country})" }
+                        div { class: "datetime", "{a.event} | {a.category} {a.round}{rank}" }
+                    }
+                }
+            })
+        },
+        Some(None) => rsx! { "Could not load the events" },
+        None => rsx! { "Loading..." },
+    };
+
+    cx.render(rsx! {
+        h1 { "Your athletes today" }
+        appearances
+    })
+}
+
+/// Buttons to show only the events of a discipline or league, and a field to
+/// show only the categories with a name, like "Women"
+pub fn FilterBar(cx: Scope<ShortSeason>) -> Element {
+    let filter = use_shared_state::<Filter>(cx).unwrap();
+    let save_error = use_shared_state::<Option<SaveError>>(cx).unwrap();
+    let current = filter.read().clone();
+
+    let update = move |change: &dyn Fn(&mut Filter)| {
+        let mut new = filter.read().clone();
+        change(&mut new);
+        *save_error.write() = new.save().err();
+        *filter.write() = new;
+    };
+
+    let disciplines = DISCIPLINES.iter().map(|d| {
+        let selected = current.discipline.as_deref() == Some(*d);
+        rsx! {
+            button {
+                class: if selected { "selected" } else { "" },
+                onclick: move |_| update(&|f| f.discipline = (!selected).then(|| d.to_string())),
+                "{d}"
+            }
+        }
+    });
+
+    let leagues = cx.props.leagues.iter().map(|l| {
+        let selected = current.league.as_ref() == Some(&l.name);
+        let name = l.name.clone();
+        rsx! {
+            button {
+                class: if selected { "selected" } else { "" },
+                onclick: move |_| update(&|f| f.league = (!selected).then(|| name.clone())),
+                "{l.name}"
+            }
+        }
+    });
+
+    let category = current.category.clone().unwrap_or_default();
+
+    cx.render(rsx! {
+        div {
+            class: "actions filter",
+            disciplines
+        }
+        div {
+            class: "actions filter",
+            leagues
+        }
+        div {
+            class: "form",
+            input {
+                placeholder: "Category, like Women",
+                value: "{category}",
+                // The filter is saved once the category is entered, not on every
+                // key
+                oninput: move |e| filter.write().category = (!e.value.is_empty()).then(|| e.value.clone()),
+                onchange: move |_| *save_error.write() = filter.read().save().err(),
+            }
+            if !current.is_empty() {
+                rsx! { button { onclick: move |_| update(&|f| *f = Filter::default()), "Show everything" } }
+            }
+        }
     })
 }
 
 pub fn RelevantEvents(cx: Scope<ShortSeason>) -> Element {
     let url = format!("seasons/{}", cx.props.id);
     let future = use_future(cx, (&cx.props.id,), |_| api::request::<Season>(url));
+    let filter = use_shared_state::<Filter>(cx).unwrap();
     
     let now = chrono::offset::Utc::now();
 
@@ -161,7 +411,7 @@ pub fn RelevantEvents(cx: Scope<ShortSeason>) -> Element {
         match future.value() {
             Some(Some(season)) => {
                 let events = season.events.clone();
-                let mut events: Vec<_> = events.into_iter().filter(|e| f(e.clone())).collect();
+                let mut events: Vec<_> = events.into_iter().filter(|e| filter.read().matches_event(e) && f(e.clone())).collect();
                 if let Some(n) = n {
                     events.truncate(n);
                 }
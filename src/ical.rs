@@ -0,0 +1,270 @@
+//! Calendars in the iCalendar format (RFC 5545) with the events of a season
+//! and the schedule of their rounds, so that they can be subscribed to in
+//! calendar apps.
+//!
+//! The output only depends on the data from the API, so exporting the same
+//! data twice gives the same file and subscriptions only change when the
+//! schedule does.
+use crate::api::{
+    self,
+    seasons::{Event, Season, ShortEvent},
+};
+use crate::calendar::local_dates;
+use crate::filter::Filter;
+use chrono::{DateTime, Datelike, Duration, Offset, TimeZone, Utc};
+use chrono_tz::{OffsetComponents, Tz};
+use std::collections::BTreeMap;
+
+const PRODID: &str = "-//boulderboard//boulderboard//EN";
+
+/// The format of a time without timezone, which is the local time of the
+/// `TZID` or UTC with a `Z` after it
+const TIME: &str = "%Y%m%dT%H%M%S";
+
+/// An event with its categories and rounds, if they could be loaded
+pub struct Entry {
+    pub event: ShortEvent,
+    pub details: Option<Event>,
+}
+
+/// The name of the season and all its events
+pub async fn fetch_season(id: u64) -> Option<(String, Vec<Entry>)> {
+    let season: Season = api::request(format!("seasons/{id}")).await?;
+    let mut entries = Vec::new();
+    for event in season.events {
+        let details = api::request(format!("events/{}", event.event_id)).await;
+        entries.push(Entry { event, details });
+    }
+    Some((season.name, entries))
+}
+
+pub async fn fetch_event(id: u64) -> Option<Entry> {
+    let details: Event = api::request(format!("events/{id}")).await?;
+    Some(Entry {
+        event: details.short(),
+        details: Some(details),
+    })
+}
+
+/// A round with a schedule
+struct Round<'a> {
+    id: u64,
+    summary: String,
+    starts_at: DateTime<Utc>,
+    ends_at: Option<DateTime<Utc>>,
+    event: &'a ShortEvent,
+}
+
+/// The calendar with an all-day event for every event and a timed event for
+/// every round with a schedule. The times of the rounds are in the timezone of
+/// the venue. With a filter, only the matching events and the rounds of the
+/// matching categories are in the calendar.
+pub fn calendar(name: &str, entries: &[Entry], filter: &Filter) -> String {
+    let mut entries: Vec<_> = entries
+        .iter()
+        .filter(|e| {
+            filter.matches_event(&e.event)
+                && (filter.category.is_none()
+                    || e.details.as_ref().map_or(false, |d| {
+                        d.dcats.iter().any(|c| filter.matches_category(c))
+                    }))
+        })
+        .collect();
+    entries.sort_by_key(|e| (e.event.starts_at, e.event.event_id));
+
+    let mut rounds = Vec::new();
+    for entry in &entries {
+        let Some(details) = &entry.details else {
+            continue;
+        };
+        for category in details.dcats.iter().filter(|c| filter.matches_category(c)) {
+            for round in &category.category_rounds {
+                let Some(starts_at) = round.starts_at else {
+                    continue;
+                };
+                rounds.push(Round {
+                    id: round.category_round_id,
+                    summary: format!(
+                        "{} {} - {}",
+                        category.dcat_name, round.name, entry.event.event
+                    ),
+                    starts_at,
+                    ends_at: round.ends_at,
+                    event: &entry.event,
+                });
+            }
+        }
+    }
+    rounds.sort_by_key(|r| (r.starts_at, r.id));
+
+    // Every timezone that is used needs a definition, covering the years in
+    // which it is used
+    let mut timezones = BTreeMap::new();
+    for round in &rounds {
+        if let Some(tz) = round.event.tz() {
+            let end = round.ends_at.unwrap_or(round.starts_at);
+            let years =
+                timezones
+                    .entry(tz.name())
+                    .or_insert((tz, round.starts_at.year(), end.year()));
+            years.1 = years.1.min(round.starts_at.year());
+            years.2 = years.2.max(end.year());
+        }
+    }
+
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        format!("PRODID:{PRODID}"),
+        "CALSCALE:GREGORIAN".to_string(),
+        format!("X-WR-CALNAME:{}", escape(name)),
+    ];
+
+    for (tz, first, last) in timezones.into_values() {
+        lines.extend(vtimezone(tz, first, last));
+    }
+
+    for entry in &entries {
+        let event = &entry.event;
+        let (start, end) = local_dates(event);
+        lines.extend([
+            "BEGIN:VEVENT".to_string(),
+            format!("UID:event-{}@boulderboard", event.event_id),
+            dtstamp(event),
+            format!("DTSTART;VALUE=DATE:{}", start.format("%Y%m%d")),
+            // The end of an all-day event is exclusive
+            format!(
+                "DTEND;VALUE=DATE:{}",
+                (end + Duration::days(1)).format("%Y%m%d")
+            ),
+            format!("SUMMARY:{}", escape(&event.event)),
+        ]);
+        if !event.location.is_empty() {
+            lines.push(format!("LOCATION:{}", escape(&event.location)));
+        }
+        lines.push("END:VEVENT".to_string());
+    }
+
+    for round in &rounds {
+        let tz = round.event.tz();
+        lines.extend([
+            "BEGIN:VEVENT".to_string(),
+            format!("UID:round-{}@boulderboard", round.id),
+            dtstamp(round.event),
+            format!("DTSTART{}", time(round.starts_at, tz)),
+        ]);
+        if let Some(ends_at) = round.ends_at {
+            lines.push(format!("DTEND{}", time(ends_at, tz)));
+        }
+        lines.push(format!("SUMMARY:{}", escape(&round.summary)));
+        if !round.event.location.is_empty() {
+            lines.push(format!("LOCATION:{}", escape(&round.event.location)));
+        }
+        lines.push("END:VEVENT".to_string());
+    }
+
+    lines.push("END:VCALENDAR".to_string());
+    lines.iter().map(|l| fold(l) + "\r\n").collect()
+}
+
+/// The time a calendar entry was created is required, but using the current
+/// time would change the calendar on every export. The start of the event
+/// only changes when the event is moved.
+fn dtstamp(event: &ShortEvent) -> String {
+    format!("DTSTAMP:{}Z", event.starts_at.format(TIME))
+}
+
+/// The value of a time property, including the `:`, in the timezone of the
+/// venue if it is known
+fn time(at: DateTime<Utc>, tz: Option<Tz>) -> String {
+    match tz {
+        Some(tz) => format!(";TZID={}:{}", tz.name(), at.with_timezone(&tz).format(TIME)),
+        None => format!(":{}Z", at.format(TIME)),
+    }
+}
+
+/// The definition of a timezone, with every change of the offset from the
+/// start of the first year until the end of the last year
+fn vtimezone(tz: Tz, first: i32, last: i32) -> Vec<String> {
+    let start = Utc.with_ymd_and_hms(first, 1, 1, 0, 0, 0).unwrap();
+    let end = Utc.with_ymd_and_hms(last + 1, 1, 1, 0, 0, 0).unwrap();
+
+    // The offset at the start is needed for the times before the first change
+    let mut changes = vec![(start, offset(tz, start), offset(tz, start))];
+    let mut day = start;
+    while day < end {
+        let next = day + Duration::days(1);
+        if offset(tz, day) != offset(tz, next) {
+            let (mut before, mut after) = (day, next);
+            while after - before > Duration::seconds(1) {
+                let middle = before + (after - before) / 2;
+                if offset(tz, middle) == offset(tz, before) {
+                    before = middle;
+                } else {
+                    after = middle;
+                }
+            }
+            changes.push((after, offset(tz, before), offset(tz, after)));
+        }
+        day = next;
+    }
+
+    let mut lines = vec!["BEGIN:VTIMEZONE".to_string(), format!("TZID:{}", tz.name())];
+    for (at, from, to) in changes {
+        let local = at.with_timezone(&tz);
+        let kind = if local.offset().dst_offset() == Duration::zero() {
+            "STANDARD"
+        } else {
+            "DAYLIGHT"
+        };
+        // The start is in the local time from before the change
+        let dtstart = at.naive_utc() + Duration::seconds(from.into());
+        lines.extend([
+            format!("BEGIN:{kind}"),
+            format!("DTSTART:{}", dtstart.format(TIME)),
+            format!("TZOFFSETFROM:{}", utc_offset(from)),
+            format!("TZOFFSETTO:{}", utc_offset(to)),
+            format!("TZNAME:{}", local.format("%Z")),
+            format!("END:{kind}"),
+        ]);
+    }
+    lines.push("END:VTIMEZONE".to_string());
+    lines
+}
+
+/// The offset from UTC in seconds
+fn offset(tz: Tz, at: DateTime<Utc>) -> i32 {
+    tz.offset_from_utc_datetime(&at.naive_utc())
+        .fix()
+        .local_minus_utc()
+}
+
+/// Like `+0200`
+fn utc_offset(seconds: i32) -> String {
+    let sign = if seconds < 0 { '-' } else { '+' };
+    let minutes = seconds.abs() / 60;
+    format!("{sign}{:02}{:02}", minutes / 60, minutes % 60)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Lines may not be longer than 75 bytes, longer lines continue on the next
+/// line after a space
+fn fold(line: &str) -> String {
+    let mut folded = String::new();
+    let mut length = 0;
+    for c in line.chars() {
+        if length + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            length = 1;
+        }
+        folded.push(c);
+        length += c.len_utf8();
+    }
+    folded
+}
@@ -1,9 +1,16 @@
 use crate::api;
 use crate::api::result::{DisciplineTag, Results};
 use crate::discipline::Discipline;
-use crate::discipline::{Ascent, Boulder, Lead, Score, Speed};
+use crate::discipline::{Ascent, Boulder, Lead, Score, Speed, Unknown};
+use crate::export;
+use crate::favourites::{FavouriteAthlete, Favourites};
+use crate::local::Competitions;
+use crate::notifications::{self, NotificationRules, Rules};
+use crate::ranking::{self, RankedAthlete};
+use crate::search;
+use crate::storage::SaveError;
 use dioxus::prelude::*;
-use std::cmp::Reverse;
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::time::Duration;
 
@@ -11,6 +18,7 @@ use std::time::Duration;
 pub enum LeaderboardInput {
     Api(String, u64),
     File(PathBuf),
+    Local(u64),
 }
 
 #[derive(PartialEq, Props)]
@@ -25,15 +33,31 @@ struct AthleteProps<D: Discipline> {
     last_name: String,
     country: String,
     flag: String,
-    ascents: Vec<D::Ascent>,
+    ascents: Vec<Option<D::Ascent>>,
     score: D::Score,
     active: bool,
     rank: usize,
+    official_rank: Option<u64>,
+    disagrees: bool,
+    /// Ascents that could not be read
+    problems: Vec<String>,
+    /// The paraclimbing sport class, like `B1`
+    sport_class: Option<String>,
+    sport_class_state: Option<String>,
+    /// Like `C 1, D 3`, for rounds on several routes
+    start_orders: String,
+    /// Show the score like the official results do, e.g. `3T4z 6 7`
+    official_notation: bool,
+    /// The position of the row in the table, which differs from the rank when
+    /// only some athletes are shown
+    order: usize,
 }
 
 fn Athlete<D: Discipline>(cx: Scope<AthleteProps<D>>) -> Element {
+    let favourites = use_shared_state::<Favourites>(cx).unwrap();
+    let save_error = use_shared_state::<Option<SaveError>>(cx).unwrap();
     let AthleteProps {
-        id: _,
+        id,
         first_name,
         last_name,
         ascents,
@@ -42,87 +66,321 @@ fn Athlete<D: Discipline>(cx: Scope<AthleteProps<D>>) -> Element {
         flag: _flag,
         active,
         rank,
+        official_rank,
+        disagrees,
+        problems,
+        sport_class,
+        sport_class_state,
+        start_orders,
+        official_notation,
+        order,
     } = cx.props;
 
     let initials = first_name
         .split(' ')
-        .map(|n| format!("{}.", n.chars().next().unwrap()))
+        .filter_map(|n| n.chars().next())
+        .map(|c| format!("{c}."))
         .collect::<Vec<_>>()
         .join(" ");
+    let problems = problems.join("\n");
+    let start_orders = if start_orders.is_empty() {
+        String::new()
+    } else {
+        format!("Start order: {start_orders}")
+    };
+    let sport_class = sport_class.as_ref().map(|class| {
+        let state = sport_class_state
+            .as_deref()
+            .unwrap_or("unknown")
+            .replace('_', " ");
+        rsx! { div { class: "sport-class", title: "Sport class status: {state}", "{class}" } }
+    });
+
+    let (is_favourite, is_favourite_country) = {
+        let favourites = favourites.read();
+        (favourites.is_athlete(*id), favourites.is_country(country))
+    };
+    let mut classes = Vec::new();
+    if *active {
+        classes.push("row-active");
+    } else if !problems.is_empty() {
+        classes.push("row-problem");
+    }
+    if is_favourite || is_favourite_country {
+        classes.push("row-favourite");
+    }
+    let classes = classes.join(" ");
+
+    let toggle_athlete = move |_| {
+        let toggled = favourites.write().toggle_athlete(FavouriteAthlete {
+            athlete_id: *id,
+            firstname: first_name.clone(),
+            lastname: last_name.clone(),
+            country: country.clone(),
+        });
+        *save_error.write() = toggled.err();
+    };
+    let toggle_country = move |_| {
+        *save_error.write() = favourites.write().toggle_country(country).err();
+    };
 
-    let order = rank - 1;
     cx.render(rsx! {
         div {
-            class: if *active { "row-active" } else { "" },
+            class: "{classes}",
             style: "--order: {order}",
+            button {
+                class: "favourite-toggle",
+                title: if is_favourite { "Unfollow" } else { "Follow" },
+                onclick: toggle_athlete,
+                if is_favourite { "★" } else { "☆" }
+            }
             div { class: "rank", "{rank}" }
-            div { class: "country-code", "{country}" }
-            div { class: "athlete-name", "{initials} {last_name}" }
+            if *disagrees {
+                let official = official_rank.unwrap_or_default();
+                rsx! {
+                    div {
+                        class: "rank-disagreement",
+                        title: "The official rank is {official}",
+                        "!"
+                    }
+                }
+            }
+            if !problems.is_empty() {
+                rsx! { div { class: "row-problem-marker", title: "{problems}", "?" } }
+            }
+            div {
+                class: if is_favourite_country { "country-code favourite-country" } else { "country-code" },
+                title: if is_favourite_country { "Unfollow {country}" } else { "Follow {country}" },
+                onclick: toggle_country,
+                "{country}"
+            }
+            div {
+                class: "athlete-name",
+                title: "{start_orders}",
+                "{initials} {last_name}"
+            }
+            sport_class
             div {
                 class: "ascents",
-                ascents.iter().map(|a| a.render())
+                ascents.iter().map(|a| match a {
+                    Some(a) => a.render(),
+                    None => rsx! { div { class: "ascent-unreadable", "?" } },
+                })
+            }
+            if *official_notation {
+                rsx! { div { class: "score score-official", "{score.official()}" } }
+            } else {
+                score.render()
             }
-            score.render()
         }
     })
 }
 
-fn extract_athletes<D: Discipline>(results: &Results) -> Vec<AthleteProps<D>> {
-    results
-        .ranking
-        .iter()
-        .map(|rank_athlete| {
-            let ascents = rank_athlete
-                .ascents
-                .iter()
-                .map(|a| D::Ascent::try_from(a.clone()).ok())
-                .collect::<Option<Vec<_>>>()
-                .unwrap();
-            // FIXME: Get the start order from the start list. The start order field on the RankAthlete might be
-            // missing.
-            let score = D::Score::calculate(0, &ascents);
-            AthleteProps {
-                id: rank_athlete.athlete.athlete_id,
-                first_name: rank_athlete.athlete.firstname.clone(),
-                last_name: rank_athlete.athlete.lastname.clone(),
-                ascents,
-                score,
-                active: rank_athlete.active,
-                country: rank_athlete.athlete.country.country.clone(),
-                flag: rank_athlete.athlete.country.flag_url.clone(),
-                // Rank is computed later by us, because the API uses weird unstable sorting
-                rank: 0,
-            }
-        })
-        .collect()
+impl<D: Discipline> From<RankedAthlete<D>> for AthleteProps<D> {
+    fn from(a: RankedAthlete<D>) -> Self {
+        let disagrees = a.disagrees();
+        let RankedAthlete {
+            id,
+            first_name,
+            last_name,
+            country,
+            flag,
+            ascents,
+            score,
+            active,
+            rank,
+            group: _,
+            official_rank,
+            comparable_rank: _,
+            problems,
+            sport_class,
+            sport_class_state,
+            start_orders,
+        } = a;
+        let start_orders = start_orders
+            .iter()
+            .map(|s| format!("{} {}", s.route_name, s.position))
+            .collect::<Vec<_>>()
+            .join(", ");
+        Self {
+            id,
+            first_name,
+            last_name,
+            country,
+            flag,
+            ascents,
+            score,
+            active,
+            rank,
+            official_rank,
+            disagrees,
+            problems,
+            sport_class,
+            sport_class_state,
+            start_orders,
+            official_notation: false,
+            order: rank - 1,
+        }
+    }
 }
 
-async fn fetch_results(input: &LeaderboardInput) -> Option<Results> {
+/// Rank the athletes for the table. If `only_favourites` is set, the other
+/// athletes are left out and the favourites are moved up.
+fn athletes<D: Discipline>(
+    results: &Results,
+    favourites: &Favourites,
+    only_favourites: bool,
+) -> Vec<AthleteProps<D>> {
+    let mut athletes: Vec<AthleteProps<D>> = ranking::rank::<D>(results)
+        .into_iter()
+        .map(AthleteProps::from)
+        .collect();
+    if only_favourites {
+        athletes.retain(|a| favourites.contains(a.id, &a.country));
+        let mut ranks: Vec<_> = athletes.iter().map(|a| a.rank).collect();
+        ranks.sort();
+        for a in &mut athletes {
+            a.order = ranks.iter().position(|&r| r == a.rank).unwrap();
+        }
+    }
+    athletes
+}
+
+pub async fn fetch_results(input: &LeaderboardInput) -> Option<Results> {
     match input {
         LeaderboardInput::Api(_, x) => {
             api::request::<Results>(format!("category_rounds/{x}/results/")).await
         }
         LeaderboardInput::File(x) => {
             let res = std::fs::read_to_string(x).unwrap();
-            serde_json::from_str(&res).map_err(|e| dbg!(e)).ok()
+            match serde_json::from_str(&res) {
+                Ok(results) => Some(results),
+                Err(e) => {
+                    eprintln!("Could not read {}: {e}", x.display());
+                    None
+                }
+            }
+        }
+        LeaderboardInput::Local(id) => Competitions::load().get(*id).map(|c| c.to_results()),
+    }
+}
+
+pub async fn sleep(duration: Duration) {
+    #[cfg(feature = "desktop")]
+    tokio::time::sleep(duration).await;
+    #[cfg(feature = "web")]
+    gloo_timers::future::sleep(duration).await;
+}
+
+/// Keep fetching the results every second and call `on_change` whenever they
+/// differ from the previous results, until `keep_polling` returns false.
+/// `known` are the results the caller already has, if any, so that they
+/// aren't reported again.
+pub async fn poll_results(
+    input: LeaderboardInput,
+    known: Option<Results>,
+    mut keep_polling: impl FnMut() -> bool,
+    mut on_change: impl FnMut(Option<Results>),
+) {
+    let mut previous = known.map(Some);
+    loop {
+        let results = fetch_results(&input).await;
+        if previous.as_ref() != Some(&results) {
+            previous = Some(results.clone());
+            on_change(results);
+        }
+        sleep(Duration::from_millis(1000)).await;
+        if !keep_polling() {
+            return;
         }
     }
 }
 
 pub fn Leaderboard(cx: Scope<LeaderboardProps>) -> Element {
     let results = use_state(cx, || None);
+    // Every time our rank of an athlete started to differ from the official
+    // one in this round. All of them are also kept in the log in the storage,
+    // so that we can find the cases our scoring gets wrong.
+    let disagreement_log = use_state(cx, Vec::<String>::new);
+    let official_notation = use_state(cx, || false);
+    let only_favourites = use_state(cx, || false);
+    let favourites = use_shared_state::<Favourites>(cx).unwrap();
+    let save_error = use_shared_state::<Option<SaveError>>(cx).unwrap();
+    let rules = use_shared_state::<Rules>(cx).unwrap();
+    let notification_error = use_state(cx, || None::<String>);
+    #[cfg(feature = "desktop")]
+    let export_status = use_state(cx, || None::<String>);
     let _ = use_coroutine(cx, |_: UnboundedReceiver<()>| {
         let results = results.to_owned();
         let input = cx.props.input.to_owned();
+        let disagreement_log = disagreement_log.to_owned();
+        let save_error = save_error.clone();
+        let favourites = favourites.clone();
+        let rules = rules.clone();
+        let notification_error = notification_error.to_owned();
+        let mut logged = HashMap::new();
+        let mut remembered: Vec<u64> = Vec::new();
+        let mut previous: Option<Results> = None;
+        let api_round = match &input {
+            LeaderboardInput::Api(event, id) => Some((event.clone(), *id)),
+            _ => None,
+        };
         async move {
-            loop {
-                dbg!("fetching!!");
-                results.set(fetch_results(&input).await);
-                #[cfg(feature = "desktop")]
-                tokio::time::sleep(Duration::from_millis(1000)).await;
-                #[cfg(feature = "web")]
-                gloo_timers::future::sleep(Duration::from_millis(1000)).await;
-            }
+            poll_results(input, None, || true, |r| {
+                if let (Some(previous), Some(current)) = (&previous, &r) {
+                    let title = format!("{} - {}", current.category, current.round);
+                    let changes = notifications::diff(
+                        previous,
+                        current,
+                        &favourites.read(),
+                        &rules.read(),
+                    );
+                    for n in changes {
+                        if let Err(e) = notifications::notify(&title, &n.to_string()) {
+                            notification_error.set(Some(e));
+                        }
+                    }
+                }
+                previous = r.clone();
+
+                // The athletes are only remembered again when they change, not
+                // on every new score
+                if let (Some(r), Some((event, id))) = (&r, &api_round) {
+                    let mut ids: Vec<u64> =
+                        r.ranking.iter().map(|a| a.athlete.athlete_id).collect();
+                    ids.sort_unstable();
+                    if ids != remembered {
+                        if let Err(e) = search::remember_athletes(event, *id, r) {
+                            *save_error.write() = Some(e);
+                        }
+                        remembered = ids;
+                    }
+                }
+
+                if let Some(r) = &r {
+                    let new: Vec<_> = ranking::disagreements(r)
+                        .into_iter()
+                        .filter(|d| {
+                            let ranks = (d.ours, d.official);
+                            logged.insert(d.athlete_id, ranks) != Some(ranks)
+                        })
+                        .collect();
+                    let round = format!(
+                        "{} - {} - {}",
+                        r.event.as_deref().unwrap_or_default(),
+                        r.category,
+                        r.round
+                    );
+                    let now = chrono::Utc::now();
+                    if let Err(e) = ranking::log_disagreements(&round, &new, now) {
+                        *save_error.write() = Some(e);
+                    }
+                    disagreement_log
+                        .with_mut(|log| log.extend(new.iter().map(|d| d.to_string())));
+                }
+                results.set(r)
+            })
+            .await
         }
     });
 
@@ -139,6 +397,47 @@ pub fn Leaderboard(cx: Scope<LeaderboardProps>) -> Element {
         None
     };
 
+    let export_name = export::file_name(r);
+
+    // In the browser we can only offer a download, on desktop we write the
+    // files to the export directory.
+    #[cfg(feature = "web")]
+    let export = {
+        let href = export::csv_data_url(&export::results_table(r));
+        rsx! {
+            div {
+                class: "export",
+                a { href: "{href}", download: "{export_name}.csv", "Export CSV" }
+            }
+        }
+    };
+    #[cfg(feature = "desktop")]
+    let export = {
+        let buttons = ["csv", "xlsx", "ods"].into_iter().map(|ext| {
+            let path = PathBuf::from("export").join(format!("{export_name}.{ext}"));
+            let onclick = move |_| {
+                if let Some(r) = results.get() {
+                    let written = std::fs::create_dir_all("export")
+                        .and_then(|_| export::write(&export::results_table(r), &path));
+                    export_status.set(Some(match written {
+                        Ok(()) => format!("Exported to {}", path.display()),
+                        Err(e) => format!("Could not export: {e}"),
+                    }));
+                }
+            };
+            rsx! { button { onclick: onclick, "Export {ext}" } }
+        });
+        rsx! {
+            div {
+                class: "export",
+                buttons
+                if let Some(status) = export_status.get() {
+                    rsx! { div { class: "export-status", "{status}" } }
+                }
+            }
+        }
+    };
+
     cx.render(rsx! {
         div {
             class: "info",
@@ -149,66 +448,65 @@ pub fn Leaderboard(cx: Scope<LeaderboardProps>) -> Element {
                 class: "leaderboard-round",
                 "{r.discipline} - {r.category} - {r.round}"
             }
+            div {
+                class: "notation",
+                button {
+                    onclick: move |_| official_notation.set(!official_notation.get()),
+                    if *official_notation.get() { "Detailed score" } else { "Official notation" }
+                }
+                button {
+                    onclick: move |_| only_favourites.set(!only_favourites.get()),
+                    if *only_favourites.get() { "All athletes" } else { "Only favourites" }
+                }
+            }
+            export
+            NotificationRules {}
+            if let Some(error) = notification_error.get() {
+                rsx! { div { class: "hint", "{error}" } }
+            }
+            if !disagreement_log.is_empty() {
+                rsx! {
+                    details {
+                        class: "disagreements",
+                        summary { "{disagreement_log.len()} disagreements with the official ranking" }
+                        ul {
+                            disagreement_log.iter().map(|d| rsx! { li { "{d}" } })
+                        }
+                    }
+                }
+            }
         }
         div {
             class: "table",
             match r.discipline {
                 DisciplineTag::Lead => {
-                    let athletes = extract_athletes::<Lead>(r);
-
-                    // We have to keep the nodes in the same order in the DOM
-                    // so we need a map from index to rank. Additionally, we
-                    // need Reverse, because we want the highest score first.
-                    let mut indices: Vec<_> = (0..athletes.len()).collect();
-                    indices.sort_by_key(|&i| Reverse(athletes[i].score.clone()));
-
-                    let mut ranking: Vec<_> = (0..athletes.len()).collect();
-                    ranking.sort_by_key(|&i| indices[i]);
-
-                    let rendered: Vec<_> = athletes
+                    let rendered: Vec<_> = athletes::<Lead>(r, &favourites.read(), *only_favourites.get())
                         .into_iter()
-                        .zip(ranking)
-                        .map(|(a, i)| rsx! { Athlete { rank: i+1, ..a } })
+                        .map(|a| rsx! { Athlete { key: "{a.id}", official_notation: *official_notation.get(), ..a } })
                         .collect();
 
                     rsx!{ rendered.into_iter() }
                 }
                 DisciplineTag::Boulder => {
-                    let athletes = extract_athletes::<Boulder>(r);
-
-                    // We have to keep the nodes in the same order in the DOM
-                    // so we need a map from index to rank. Additionally, we
-                    // need Reverse, because we want the highest score first.
-                    let mut indices: Vec<_> = (0..athletes.len()).collect();
-                    indices.sort_by_key(|&i| Reverse(athletes[i].score.clone()));
-
-                    let mut ranking: Vec<_> = (0..athletes.len()).collect();
-                    ranking.sort_by_key(|&i| indices[i]);
-
-                    let rendered: Vec<_> = athletes
+                    let rendered: Vec<_> = athletes::<Boulder>(r, &favourites.read(), *only_favourites.get())
                         .into_iter()
-                        .zip(ranking)
-                        .map(|(a, i)| rsx! {Athlete { key: "{a.id}", rank: i+1, ..a } })
+                        .map(|a| rsx! { Athlete { key: "{a.id}", official_notation: *official_notation.get(), ..a } })
                         .collect();
 
                     rsx!{ rendered.into_iter() }
                 }
                 DisciplineTag::Speed => {
-                    let athletes = extract_athletes::<Speed>(r);
-
-                    // We have to keep the nodes in the same order in the DOM
-                    // so we need a map from index to rank. Additionally, we
-                    // need Reverse, because we want the highest score first.
-                    let mut indices: Vec<_> = (0..athletes.len()).collect();
-                    indices.sort_by_key(|&i| Reverse(athletes[i].score.clone()));
-
-                    let mut ranking: Vec<_> = (0..athletes.len()).collect();
-                    ranking.sort_by_key(|&i| indices[i]);
+                    let rendered: Vec<_> = athletes::<Speed>(r, &favourites.read(), *only_favourites.get())
+                        .into_iter()
+                        .map(|a| rsx! { Athlete { key: "{a.id}", official_notation: *official_notation.get(), ..a } })
+                        .collect();
 
-                    let rendered: Vec<_> = athletes
+                    rsx!{ rendered.into_iter() }
+                }
+                DisciplineTag::Unknown => {
+                    let rendered: Vec<_> = athletes::<Unknown>(r, &favourites.read(), *only_favourites.get())
                         .into_iter()
-                        .zip(ranking)
-                        .map(|(a, i)| rsx! {Athlete { key: "{a.id}", rank: i+1, ..a } })
+                        .map(|a| rsx! { Athlete { key: "{a.id}", ..a } })
                         .collect();
 
                     rsx!{ rendered.into_iter() }
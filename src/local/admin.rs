@@ -0,0 +1,272 @@
+//! Pages for creating local competitions and entering their athletes, routes
+//! and scores.
+use super::{Competition, Competitions, CorrectionError};
+use crate::leaderboard::LeaderboardInput;
+use crate::storage::SaveError;
+use crate::Page;
+use dioxus::prelude::*;
+
+pub fn LocalCompetitions(cx: Scope) -> Element {
+    let competitions = use_state(cx, Competitions::load);
+    let page = use_shared_state::<Page>(cx).unwrap();
+    let save_error = use_shared_state::<Option<SaveError>>(cx).unwrap();
+    let name = use_state(cx, String::new);
+    let category = use_state(cx, String::new);
+    let round = use_state(cx, String::new);
+
+    let create = move |_| {
+        if name.get().is_empty() {
+            return;
+        }
+        let created = Competitions::create(
+            name.get().clone(),
+            category.get().clone(),
+            round.get().clone(),
+        );
+        match created {
+            Ok(id) => *page.write() = Page::CompetitionEditor(id),
+            Err(e) => *save_error.write() = Some(e),
+        }
+    };
+
+    let list = competitions.get().competitions.iter().map(|c| {
+        let id = c.id;
+        let athletes = c.athletes.len();
+        rsx! {
+            div {
+                class: "event",
+                div { "{c.name}" }
+                div {
+                    class: "datetime",
+                    "{c.category} | {c.round} | {athletes} athletes"
+                }
+                div {
+                    class: "actions",
+                    button {
+                        onclick: move |_| *page.write() = Page::CompetitionEditor(id),
+                        "Edit"
+                    }
+                    button {
+                        onclick: move |_| {
+                            *page.write() = Page::Leaderboard(LeaderboardInput::Local(id))
+                        },
+                        "Leaderboard"
+                    }
+                    button {
+                        onclick: move |_| {
+                            *save_error.write() = Competitions::remove(id).err();
+                            competitions.set(Competitions::load());
+                        },
+                        "Delete"
+                    }
+                }
+            }
+        }
+    });
+
+    cx.render(rsx! {
+        h1 { "Local competitions" }
+        list
+        h2 { "New competition" }
+        div {
+            class: "form",
+            input {
+                placeholder: "Name",
+                value: "{name}",
+                oninput: move |e| name.set(e.value.clone()),
+            }
+            input {
+                placeholder: "Category",
+                value: "{category}",
+                oninput: move |e| category.set(e.value.clone()),
+            }
+            input {
+                placeholder: "Round",
+                value: "{round}",
+                oninput: move |e| round.set(e.value.clone()),
+            }
+            button { onclick: create, "Create" }
+        }
+    })
+}
+
+#[derive(PartialEq, Props)]
+pub struct CompetitionEditorProps {
+    id: u64,
+}
+
+pub fn CompetitionEditor(cx: Scope<CompetitionEditorProps>) -> Element {
+    let id = cx.props.id;
+    let competition = use_state(cx, || Competitions::load().get(id).cloned());
+    let page = use_shared_state::<Page>(cx).unwrap();
+    let save_error = use_shared_state::<Option<SaveError>>(cx).unwrap();
+    let correction_error = use_state(cx, || None::<CorrectionError>);
+    let firstname = use_state(cx, String::new);
+    let lastname = use_state(cx, String::new);
+    let country = use_state(cx, String::new);
+    let route_name = use_state(cx, String::new);
+
+    let Some(c) = competition.get() else {
+        return cx.render(rsx! { "Competition not found" });
+    };
+
+    // Changes that could not be saved are not shown
+    let show = move |updated: Result<Option<Competition>, SaveError>| match updated {
+        Ok(updated) => competition.set(updated),
+        Err(e) => *save_error.write() = Some(e),
+    };
+
+    let add_athlete = move |_| {
+        if firstname.get().is_empty() || lastname.get().is_empty() {
+            return;
+        }
+        show(Competitions::update(id, |c| {
+            c.add_athlete(
+                firstname.get().clone(),
+                lastname.get().to_uppercase(),
+                country.get().to_uppercase(),
+            )
+        }));
+        firstname.set(String::new());
+        lastname.set(String::new());
+    };
+
+    let add_route = move |_| {
+        if route_name.get().is_empty() {
+            return;
+        }
+        show(Competitions::update(id, |c| {
+            c.add_route(route_name.get().clone())
+        }));
+        route_name.set(String::new());
+    };
+
+    // Scores are entered as the number of the attempt in which the zone or
+    // top was reached. An empty field means that it was not reached. The
+    // correction is applied once the field is left, not for every digit.
+    let set_score = move |athlete_id: u64, route: usize, top: bool, value: &str| {
+        let value = value.parse().ok();
+        let mut corrected = Ok(());
+        show(Competitions::update(id, |c| {
+            let card = c.scorecard_mut(athlete_id, route);
+            corrected = if top {
+                card.correct(card.zone, value)
+            } else {
+                card.correct(value, card.top)
+            };
+        }));
+        correction_error.set(corrected.err());
+    };
+
+    // The route names lead to the scorecard for the judge of that route
+    let routes = c.routes.iter().enumerate().map(|(route, r)| {
+        rsx! {
+            button {
+                class: "scorecard-route",
+                onclick: move |_| *page.write() = Page::Judge(id, route),
+                "{r}"
+            }
+        }
+    });
+
+    let athletes = c.athletes.iter().map(|a| {
+        let athlete_id = a.id;
+        let cards = (0..c.routes.len()).map(move |route| {
+            let card = c.scorecard(athlete_id, route);
+            let zone = card
+                .and_then(|s| s.zone)
+                .map(|z| z.to_string())
+                .unwrap_or_default();
+            let top = card
+                .and_then(|s| s.top)
+                .map(|t| t.to_string())
+                .unwrap_or_default();
+            rsx! {
+                div {
+                    class: "scorecard-route",
+                    input {
+                        r#type: "number",
+                        placeholder: "Z",
+                        value: "{zone}",
+                        onchange: move |e| set_score(athlete_id, route, false, &e.value),
+                    }
+                    input {
+                        r#type: "number",
+                        placeholder: "T",
+                        value: "{top}",
+                        onchange: move |e| set_score(athlete_id, route, true, &e.value),
+                    }
+                }
+            }
+        });
+        rsx! {
+            div {
+                class: "scorecard-row",
+                div { class: "country-code", "{a.country}" }
+                div { class: "athlete-name", "{a.firstname} {a.lastname}" }
+                cards
+                button {
+                    onclick: move |_| {
+                        show(Competitions::update(id, |c| c.remove_athlete(athlete_id)))
+                    },
+                    "Remove"
+                }
+            }
+        }
+    });
+
+    cx.render(rsx! {
+        h1 { "{c.name}" }
+        div {
+            class: "actions",
+            button {
+                onclick: move |_| *page.write() = Page::Leaderboard(LeaderboardInput::Local(id)),
+                "Leaderboard"
+            }
+        }
+        h2 { "Scores" }
+        if let Some(e) = correction_error.get() {
+            rsx! { div { class: "hint", "{e}" } }
+        }
+        div {
+            class: "scorecards",
+            div {
+                class: "scorecard-row",
+                div { class: "country-code" }
+                div { class: "athlete-name" }
+                routes
+            }
+            athletes
+        }
+        h2 { "Add athlete" }
+        div {
+            class: "form",
+            input {
+                placeholder: "First name",
+                value: "{firstname}",
+                oninput: move |e| firstname.set(e.value.clone()),
+            }
+            input {
+                placeholder: "Last name",
+                value: "{lastname}",
+                oninput: move |e| lastname.set(e.value.clone()),
+            }
+            input {
+                placeholder: "Country",
+                value: "{country}",
+                oninput: move |e| country.set(e.value.clone()),
+            }
+            button { onclick: add_athlete, "Add" }
+        }
+        h2 { "Add route" }
+        div {
+            class: "form",
+            input {
+                placeholder: "Name",
+                value: "{route_name}",
+                oninput: move |e| route_name.set(e.value.clone()),
+            }
+            button { onclick: add_route, "Add" }
+        }
+    })
+}
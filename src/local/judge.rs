@@ -0,0 +1,145 @@
+//! The scorecard of a single boulder, used by the judge of that boulder
+use super::{Competition, Competitions, JudgeAction};
+use crate::api::result::Status;
+use crate::leaderboard::sleep;
+use crate::storage::SaveError;
+use dioxus::prelude::*;
+use std::time::Duration;
+
+#[derive(PartialEq, Props)]
+pub struct JudgeProps {
+    id: u64,
+    route: usize,
+}
+
+pub fn Judge(cx: Scope<JudgeProps>) -> Element {
+    let JudgeProps { id, route } = *cx.props;
+    let competition = use_state(cx, || Competitions::load().get(id).cloned());
+    let selected = use_state(cx, || None::<u64>);
+    let save_error = use_shared_state::<Option<SaveError>>(cx).unwrap();
+
+    // Other judges might be entering results at the same time, so we keep
+    // reloading the competition.
+    let _ = use_coroutine(cx, |_: UnboundedReceiver<()>| {
+        let competition = competition.to_owned();
+        async move {
+            loop {
+                sleep(Duration::from_millis(1000)).await;
+                competition.set(Competitions::load().get(id).cloned());
+            }
+        }
+    });
+
+    let Some(c) = competition.get() else {
+        return cx.render(rsx! { "Competition not found" });
+    };
+    let Some(route_name) = c.routes.get(route) else {
+        return cx.render(rsx! { "Route not found" });
+    };
+
+    // Changes that could not be saved are not shown
+    let show = move |updated: Result<Option<Competition>, SaveError>| match updated {
+        Ok(updated) => competition.set(updated),
+        Err(e) => *save_error.write() = Some(e),
+    };
+
+    // Default to the athlete that is on the wall
+    let athlete_id = selected.get().or_else(|| c.active_athlete(route));
+
+    let act = move |action: JudgeAction| {
+        if let Some(athlete_id) = athlete_id {
+            show(Competitions::update(id, |c| {
+                c.scorecard_mut(athlete_id, route).apply(action);
+            }));
+        }
+    };
+
+    let athletes = c.athletes.iter().map(|a| {
+        let a_id = a.id;
+        let status = match c.scorecard(a_id, route).map(|s| &s.status) {
+            Some(Status::Active) => "On the wall",
+            Some(Status::Locked) => "Locked",
+            Some(Status::Confirmed) => "Confirmed",
+            Some(Status::Pending | Status::Unknown) | None => "",
+        };
+        let selected_class = if athlete_id == Some(a_id) {
+            "expanded"
+        } else {
+            ""
+        };
+        rsx! {
+            div {
+                class: "event {selected_class}",
+                onclick: move |_| {
+                    selected.set(Some(a_id));
+                    show(Competitions::update(id, |c| c.start(a_id, route)));
+                },
+                div { "{a.firstname} {a.lastname}" }
+                div { class: "datetime", "{a.country} {status}" }
+            }
+        }
+    });
+
+    let card = athlete_id.and_then(|a_id| {
+        let athlete = c.athletes.iter().find(|a| a.id == a_id)?;
+        let card = c.scorecard(a_id, route);
+        let attempts = card.map(|s| s.attempts).unwrap_or_default();
+        let zone = card
+            .and_then(|s| s.zone)
+            .map(|z| z.to_string())
+            .unwrap_or_else(|| "-".into());
+        let top = card
+            .and_then(|s| s.top)
+            .map(|t| t.to_string())
+            .unwrap_or_else(|| "-".into());
+        let status = match card.map(|s| &s.status) {
+            Some(Status::Active) => "Active",
+            Some(Status::Locked) => "Locked",
+            Some(Status::Confirmed) => "Confirmed",
+            Some(Status::Pending | Status::Unknown) | None => "Pending",
+        };
+        let can_undo = card.map(|s| s.can_undo()).unwrap_or(false);
+
+        Some(rsx! {
+            div {
+                class: "judge-card",
+                div { class: "athlete-name", "{athlete.firstname} {athlete.lastname}" }
+                div { class: "judge-status", "{status}" }
+                div {
+                    class: "judge-marks",
+                    div { "Attempts" div { class: "score", "{attempts}" } }
+                    div { "Zone" div { class: "score", "{zone}" } }
+                    div { "Top" div { class: "score", "{top}" } }
+                }
+                div {
+                    class: "judge-actions",
+                    button { onclick: move |_| act(JudgeAction::Attempt), "Attempt" }
+                    button { onclick: move |_| act(JudgeAction::Zone), "Zone" }
+                    button { onclick: move |_| act(JudgeAction::Top), "Top" }
+                }
+                div {
+                    class: "judge-actions",
+                    button {
+                        disabled: "{!can_undo}",
+                        onclick: move |_| {
+                            show(Competitions::update(id, |c| {
+                                c.scorecard_mut(a_id, route).undo()
+                            }))
+                        },
+                        "Undo"
+                    }
+                    button { onclick: move |_| act(JudgeAction::Finish), "Finish" }
+                    button { onclick: move |_| act(JudgeAction::Lock), "Lock" }
+                    button { onclick: move |_| act(JudgeAction::Confirm), "Confirm" }
+                }
+            }
+        })
+    });
+
+    cx.render(rsx! {
+        h1 { "{c.name} - Boulder {route_name}" }
+        card
+        h2 { "Athletes" }
+        athletes
+    })
+}
@@ -0,0 +1,433 @@
+//! Competitions that are scored locally by our own judges, instead of being
+//! fetched from the IFSC.
+//!
+//! Local competitions are always scored as boulder rounds. They produce the
+//! same [`Results`] as the API, so that they can be shown by the regular
+//! leaderboard.
+//!
+//! The competitions are kept in the local storage of a single device, so
+//! judges can only work concurrently in several windows of the same app.
+//! Judges on different devices don't see each other's changes.
+use crate::api::result::{
+    Ascent, Athlete, BoulderAscent, Country, DisciplineTag, LeadAscent, RankAthlete, Results,
+    SpeedAscent, Status,
+};
+use crate::storage::{self, SaveError};
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use std::sync::Mutex;
+
+mod admin;
+mod judge;
+
+pub use admin::{CompetitionEditor, LocalCompetitions};
+pub use judge::Judge;
+
+const STORAGE_KEY: &str = "competitions";
+
+/// The number of actions of a scorecard that can be undone
+const HISTORY_LIMIT: usize = 20;
+
+/// Held while the competitions are loaded, changed and saved again, so that
+/// windows that save at the same time don't overwrite each other's changes
+static STORAGE_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct Competitions {
+    next_id: u64,
+    pub competitions: Vec<Competition>,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq)]
+pub struct Competition {
+    pub id: u64,
+    pub name: String,
+    pub category: String,
+    pub round: String,
+    pub athletes: Vec<LocalAthlete>,
+    pub routes: Vec<String>,
+    pub scorecards: Vec<Scorecard>,
+    next_athlete_id: u64,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq)]
+pub struct LocalAthlete {
+    pub id: u64,
+    pub firstname: String,
+    pub lastname: String,
+    pub country: String,
+}
+
+/// The result of a single athlete on a single route
+#[derive(Clone, Serialize, Deserialize, PartialEq)]
+pub struct Scorecard {
+    pub athlete_id: u64,
+    /// Index into the routes of the competition
+    pub route: usize,
+    pub attempts: u64,
+    /// The attempt in which the zone was reached
+    pub zone: Option<u64>,
+    /// The attempt in which the top was reached
+    pub top: Option<u64>,
+    pub status: Status,
+    /// Previous states of the scorecard, to undo the actions of judges
+    #[serde(default)]
+    history: Vec<Snapshot>,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq)]
+struct Snapshot {
+    attempts: u64,
+    zone: Option<u64>,
+    top: Option<u64>,
+    status: Status,
+}
+
+/// The actions a judge can take on a scorecard.
+///
+/// The status of a scorecard follows the same lifecycle as the API: the
+/// athlete is `Active` while on the wall, the result is `Pending` until the
+/// judge locks it and finally it gets `Confirmed`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum JudgeAction {
+    Attempt,
+    Zone,
+    Top,
+    Finish,
+    Lock,
+    Confirm,
+}
+
+/// Why an admin could not correct a scorecard
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CorrectionError {
+    /// The athlete is on the wall, so the judge of the route is scoring
+    Active,
+    /// Locked and confirmed results cannot be changed anymore
+    Locked,
+    NoAttempt,
+    ZoneAfterTop,
+}
+
+impl Display for CorrectionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Active => "The athlete is on the wall, the judge is scoring",
+            Self::Locked => "The result is locked",
+            Self::NoAttempt => "The first attempt is attempt 1",
+            Self::ZoneAfterTop => "The zone can't be reached after the top",
+        })
+    }
+}
+
+impl Competitions {
+    pub fn load() -> Self {
+        storage::load(STORAGE_KEY).unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<(), SaveError> {
+        storage::save(STORAGE_KEY, self)
+    }
+
+    pub fn get(&self, id: u64) -> Option<&Competition> {
+        self.competitions.iter().find(|c| c.id == id)
+    }
+
+    /// Create a new competition and return its id
+    pub fn create(name: String, category: String, round: String) -> Result<u64, SaveError> {
+        let _lock = STORAGE_LOCK.lock().unwrap();
+        let mut all = Self::load();
+        let id = all.next_id;
+        all.next_id += 1;
+        all.competitions
+            .push(Competition::new(id, name, category, round));
+        all.save()?;
+        Ok(id)
+    }
+
+    /// Apply a change to a competition and store it.
+    ///
+    /// This reloads the competitions first, so that we don't overwrite changes
+    /// made in other windows in the meantime. Returns the updated competition,
+    /// `None` if it doesn't exist anymore.
+    pub fn update(
+        id: u64,
+        f: impl FnOnce(&mut Competition),
+    ) -> Result<Option<Competition>, SaveError> {
+        let _lock = STORAGE_LOCK.lock().unwrap();
+        let mut all = Self::load();
+        let Some(competition) = all.competitions.iter_mut().find(|c| c.id == id) else {
+            return Ok(None);
+        };
+        f(competition);
+        let competition = competition.clone();
+        all.save()?;
+        Ok(Some(competition))
+    }
+
+    pub fn remove(id: u64) -> Result<(), SaveError> {
+        let _lock = STORAGE_LOCK.lock().unwrap();
+        let mut all = Self::load();
+        all.competitions.retain(|c| c.id != id);
+        all.save()
+    }
+}
+
+impl Competition {
+    pub fn new(id: u64, name: String, category: String, round: String) -> Self {
+        Self {
+            id,
+            name,
+            category,
+            round,
+            athletes: Vec::new(),
+            routes: Vec::new(),
+            scorecards: Vec::new(),
+            next_athlete_id: 0,
+        }
+    }
+
+    pub fn add_athlete(&mut self, firstname: String, lastname: String, country: String) {
+        self.athletes.push(LocalAthlete {
+            id: self.next_athlete_id,
+            firstname,
+            lastname,
+            country,
+        });
+        self.next_athlete_id += 1;
+    }
+
+    pub fn remove_athlete(&mut self, id: u64) {
+        self.athletes.retain(|a| a.id != id);
+        self.scorecards.retain(|s| s.athlete_id != id);
+    }
+
+    pub fn add_route(&mut self, name: String) {
+        self.routes.push(name);
+    }
+
+    pub fn scorecard(&self, athlete_id: u64, route: usize) -> Option<&Scorecard> {
+        self.scorecards
+            .iter()
+            .find(|s| s.athlete_id == athlete_id && s.route == route)
+    }
+
+    /// Get the scorecard of the athlete on the route, creating it if it does
+    /// not exist yet.
+    pub fn scorecard_mut(&mut self, athlete_id: u64, route: usize) -> &mut Scorecard {
+        let index = match self
+            .scorecards
+            .iter()
+            .position(|s| s.athlete_id == athlete_id && s.route == route)
+        {
+            Some(index) => index,
+            None => {
+                self.scorecards.push(Scorecard {
+                    athlete_id,
+                    route,
+                    attempts: 0,
+                    zone: None,
+                    top: None,
+                    status: Status::Pending,
+                    history: Vec::new(),
+                });
+                self.scorecards.len() - 1
+            }
+        };
+        &mut self.scorecards[index]
+    }
+
+    /// Put the athlete on the wall of the route. Any other athlete that was
+    /// still active on that route is finished.
+    pub fn start(&mut self, athlete_id: u64, route: usize) {
+        for card in &mut self.scorecards {
+            if card.route == route && card.athlete_id != athlete_id {
+                card.apply(JudgeAction::Finish);
+            }
+        }
+
+        let card = self.scorecard_mut(athlete_id, route);
+        if card.status == Status::Pending {
+            card.push_history();
+            card.status = Status::Active;
+        }
+    }
+
+    /// The athlete that is currently on the wall of the route
+    pub fn active_athlete(&self, route: usize) -> Option<u64> {
+        self.scorecards
+            .iter()
+            .find(|s| s.route == route && s.status == Status::Active)
+            .map(|s| s.athlete_id)
+    }
+
+    /// Convert the competition into the same results as the API would give
+    pub fn to_results(&self) -> Results {
+        let ranking = self
+            .athletes
+            .iter()
+            .map(|athlete| {
+                let ascents = (0..self.routes.len())
+                    .map(|route| match self.scorecard(athlete.id, route) {
+                        Some(card) => card.to_ascent(),
+                        None => Ascent {
+                            status: Status::Pending,
+                            boulder: BoulderAscent {
+                                top: Some(false),
+                                top_tries: None,
+                                zone: Some(false),
+                                zone_tries: None,
+                            },
+                            lead: LeadAscent::default(),
+                            speed: SpeedAscent::default(),
+                        },
+                    })
+                    .collect::<Vec<_>>();
+                RankAthlete {
+                    athlete: Athlete {
+                        athlete_id: athlete.id,
+                        firstname: athlete.firstname.clone(),
+                        lastname: athlete.lastname.clone(),
+                        country: Country {
+                            country: athlete.country.clone(),
+                            flag_url: String::new(),
+                        },
+                    },
+                    active: ascents.iter().any(|a| a.status == Status::Active),
+                    ascents,
+                    rank: None,
+                    score: None,
+                    starting_group: None,
+                    group_rank: None,
+                    paraclimbing_sport_class: None,
+                    sport_class_state: None,
+                    route_start_orders: Vec::new(),
+                }
+            })
+            .collect();
+
+        Results {
+            discipline: DisciplineTag::Boulder,
+            event: Some(self.name.clone()),
+            category: self.category.clone(),
+            round: self.round.clone(),
+            ranking,
+        }
+    }
+}
+
+impl Scorecard {
+    /// Apply the action if it is allowed in the current status. Returns
+    /// whether it was applied.
+    pub fn apply(&mut self, action: JudgeAction) -> bool {
+        let allowed = match action {
+            JudgeAction::Attempt | JudgeAction::Finish => self.status == Status::Active,
+            JudgeAction::Zone => self.status == Status::Active && self.zone.is_none(),
+            JudgeAction::Top => self.status == Status::Active && self.top.is_none(),
+            JudgeAction::Lock => self.status == Status::Pending,
+            JudgeAction::Confirm => self.status == Status::Locked,
+        };
+        if !allowed {
+            return false;
+        }
+
+        self.push_history();
+
+        // Marking a zone or top without an attempt counts as the first attempt
+        let attempt = self.attempts.max(1);
+        match action {
+            JudgeAction::Attempt => self.attempts += 1,
+            JudgeAction::Zone => {
+                self.attempts = attempt;
+                self.zone = Some(attempt);
+            }
+            JudgeAction::Top => {
+                self.attempts = attempt;
+                self.zone = self.zone.or(Some(attempt));
+                self.top = Some(attempt);
+            }
+            JudgeAction::Finish => self.status = Status::Pending,
+            JudgeAction::Lock => self.status = Status::Locked,
+            JudgeAction::Confirm => self.status = Status::Confirmed,
+        }
+        true
+    }
+
+    /// Set the attempts in which the zone and top were reached, like for
+    /// results that were written down on paper. A top without a zone implies
+    /// the zone. Like the actions of judges, this can be undone.
+    pub fn correct(&mut self, zone: Option<u64>, top: Option<u64>) -> Result<(), CorrectionError> {
+        match self.status {
+            Status::Active => return Err(CorrectionError::Active),
+            Status::Locked | Status::Confirmed => return Err(CorrectionError::Locked),
+            Status::Pending | Status::Unknown => {}
+        }
+        if zone == Some(0) || top == Some(0) {
+            return Err(CorrectionError::NoAttempt);
+        }
+        let zone = zone.or(top);
+        if let (Some(zone), Some(top)) = (zone, top) {
+            if zone > top {
+                return Err(CorrectionError::ZoneAfterTop);
+            }
+        }
+
+        self.push_history();
+        self.attempts = self.attempts.max(zone.unwrap_or(0)).max(top.unwrap_or(0));
+        self.zone = zone;
+        self.top = top;
+        Ok(())
+    }
+
+    /// Revert the last action. Locked and confirmed results cannot be changed
+    /// anymore.
+    pub fn undo(&mut self) {
+        if !self.can_undo() {
+            return;
+        }
+        if let Some(Snapshot {
+            attempts,
+            zone,
+            top,
+            status,
+        }) = self.history.pop()
+        {
+            self.attempts = attempts;
+            self.zone = zone;
+            self.top = top;
+            self.status = status;
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !matches!(self.status, Status::Locked | Status::Confirmed) && !self.history.is_empty()
+    }
+
+    fn push_history(&mut self) {
+        if self.history.len() >= HISTORY_LIMIT {
+            self.history.remove(0);
+        }
+        self.history.push(Snapshot {
+            attempts: self.attempts,
+            zone: self.zone,
+            top: self.top,
+            status: self.status.clone(),
+        });
+    }
+
+    fn to_ascent(&self) -> Ascent {
+        // Reaching the top implies reaching the zone
+        let zone = self.zone.or(self.top);
+        Ascent {
+            status: self.status.clone(),
+            boulder: BoulderAscent {
+                top: Some(self.top.is_some()),
+                top_tries: self.top,
+                zone: Some(zone.is_some()),
+                zone_tries: zone,
+            },
+            lead: LeadAscent::default(),
+            speed: SpeedAscent::default(),
+        }
+    }
+}
@@ -1,12 +1,43 @@
 #![allow(non_snake_case)]
 mod api;
+mod calendar;
+#[cfg(feature = "desktop")]
+mod cli;
 mod discipline;
+mod export;
+mod favourites;
+mod filter;
+mod head_to_head;
+#[cfg(feature = "desktop")]
+mod history;
 mod home;
+mod ical;
 mod leaderboard;
+mod local;
+mod notifications;
+mod predictions;
+mod ranking;
+mod rating;
+mod search;
+#[cfg(feature = "server")]
+mod server;
+mod standings;
+mod storage;
+mod table;
+#[cfg(test)]
+mod tests;
 
 use crate::{
-    home::Home,
+    api::seasons::{ShortCategory, ShortEvent, ShortSeason},
+    calendar::Calendar,
+    head_to_head::HeadToHead,
+    home::{EventPage, Home, SeasonPage},
     leaderboard::{Leaderboard, LeaderboardInput},
+    local::{CompetitionEditor, Judge, LocalCompetitions},
+    predictions::PredictionGame,
+    rating::AthletePage,
+    search::Search,
+    standings::{Countries, CountryScope, EventCategory, Standings},
 };
 use clap::Parser;
 use dioxus::prelude::*;
@@ -18,6 +49,16 @@ struct Args {
     /// Read data from a local file instead of from the API
     #[arg(short, long, group = "input")]
     file: Option<PathBuf>,
+
+    /// Print the requested data instead of launching the UI
+    #[cfg(feature = "desktop")]
+    #[command(subcommand)]
+    command: Option<cli::Command>,
+
+    /// Output format for the commands
+    #[cfg(feature = "desktop")]
+    #[arg(long, value_enum, default_value_t, global = true)]
+    format: table::Format,
 }
 
 #[derive(PartialEq, Props)]
@@ -29,11 +70,30 @@ struct AppState {
 enum Page {
     Home,
     Leaderboard(LeaderboardInput),
+    LocalCompetitions,
+    CompetitionEditor(u64),
+    Judge(u64, usize),
+    Standings(ShortSeason),
+    Calendar(ShortSeason),
+    Countries(CountryScope),
+    Season(ShortSeason),
+    Event(ShortEvent),
+    EventCategory(ShortEvent, ShortCategory),
+    Search,
+    HeadToHead,
+    Athlete(u64),
+    PredictionGame,
 }
 
 fn main() {
     let args = Args::parse();
 
+    #[cfg(feature = "desktop")]
+    if let Some(command) = args.command {
+        cli::run(command, args.format);
+        return;
+    }
+
     let page = match args.file {
         Some(x) => Page::Leaderboard(LeaderboardInput::File(x)),
         _ => Page::Home,
@@ -58,14 +118,50 @@ const FONT: &str = r#"
 
 fn App(cx: Scope<AppState>) -> Element {
     use_shared_state_provider(cx, || cx.props.page.clone());
+    use_shared_state_provider(cx, favourites::Favourites::load);
+    use_shared_state_provider(cx, filter::Filter::load);
+    use_shared_state_provider(cx, notifications::Rules::load);
+    use_shared_state_provider(cx, || None::<storage::SaveError>);
+    use_shared_state_provider(cx, rating::SharedRatings::default);
+    let ratings = use_shared_state::<rating::SharedRatings>(cx).unwrap();
+    // The ratings are computed once for every page that shows them
+    use_future(cx, (), |_| {
+        let ratings = ratings.clone();
+        async move { *ratings.write() = rating::SharedRatings::Loaded(rating::load().await) }
+    });
     let page = use_shared_state::<Page>(cx).unwrap();
+    let save_error = use_shared_state::<Option<storage::SaveError>>(cx).unwrap();
+    let save_error_message = save_error.read().as_ref().map(|e| e.to_string());
     cx.render(rsx! {
         head { dangerous_inner_html: "{FONT}", title { "Boulderboard" } }
         style { include_str!("../public/style.css") }
         div { onclick: move |_| *page.write() = Page::Home, class: "header", "Boulderboard"}
+        if let Some(message) = save_error_message {
+            rsx! {
+                div {
+                    class: "save-error",
+                    title: "Dismiss",
+                    onclick: move |_| *save_error.write() = None,
+                    "{message}"
+                }
+            }
+        }
         match page.read().clone() {
             Page::Home => rsx! { Home {} },
             Page::Leaderboard(input) => rsx! { Leaderboard { input: input.clone() } },
+            Page::LocalCompetitions => rsx! { LocalCompetitions {} },
+            Page::CompetitionEditor(id) => rsx! { CompetitionEditor { id: id } },
+            Page::Judge(id, route) => rsx! { Judge { id: id, route: route } },
+            Page::Standings(season) => rsx! { Standings { ..season } },
+            Page::Calendar(season) => rsx! { Calendar { ..season } },
+            Page::Countries(scope) => rsx! { Countries { scope: scope } },
+            Page::Season(season) => rsx! { SeasonPage { ..season } },
+            Page::Event(event) => rsx! { EventPage { ..event } },
+            Page::EventCategory(event, category) => rsx! { EventCategory { event: event, category: category } },
+            Page::Search => rsx! { Search {} },
+            Page::HeadToHead => rsx! { HeadToHead {} },
+            Page::Athlete(id) => rsx! { AthletePage { id: id } },
+            Page::PredictionGame => rsx! { PredictionGame {} },
         }
     })
 }
\ No newline at end of file
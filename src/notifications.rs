@@ -0,0 +1,256 @@
+//! Notifications about favourite athletes and rounds, found by comparing the
+//! successive results of a round while it is shown.
+use crate::api::result::{self, DisciplineTag, Results, Status};
+use crate::discipline::{Boulder, Discipline, Lead, Speed, Unknown};
+use crate::favourites::Favourites;
+use crate::ranking;
+use crate::storage::{self, SaveError};
+use dioxus::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+const STORAGE_KEY: &str = "notifications";
+
+/// The number of athletes of a semi-final that go through to the final
+pub const FINALS_SPOTS: usize = 8;
+
+/// Which notifications the user wants to get
+#[derive(Clone, Serialize, Deserialize, PartialEq)]
+pub struct Rules {
+    /// A favourite athlete topped a route
+    pub topped: bool,
+    /// A favourite athlete moved into one of the [`FINALS_SPOTS`] of a
+    /// semi-final
+    pub finals_spot: bool,
+    pub round_started: bool,
+    pub round_finished: bool,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Self {
+            topped: true,
+            finals_spot: true,
+            round_started: true,
+            round_finished: true,
+        }
+    }
+}
+
+impl Rules {
+    pub fn load() -> Self {
+        storage::load(STORAGE_KEY).unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<(), SaveError> {
+        storage::save(STORAGE_KEY, self)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Notification {
+    /// The route is counted from 1
+    Topped {
+        athlete: String,
+        route: usize,
+    },
+    FinalsSpot {
+        athlete: String,
+        rank: usize,
+    },
+    RoundStarted,
+    RoundFinished,
+}
+
+impl Display for Notification {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Topped { athlete, route } => write!(f, "{athlete} topped route {route}"),
+            Self::FinalsSpot { athlete, rank } => {
+                write!(f, "{athlete} moved into a finals spot at rank {rank}")
+            }
+            Self::RoundStarted => f.write_str("The round has started"),
+            Self::RoundFinished => f.write_str("The round has finished"),
+        }
+    }
+}
+
+/// The notifications for the changes between two results of the same round
+pub fn diff(
+    previous: &Results,
+    current: &Results,
+    favourites: &Favourites,
+    rules: &Rules,
+) -> Vec<Notification> {
+    let mut notifications = Vec::new();
+
+    if rules.round_started && !started(previous) && started(current) {
+        notifications.push(Notification::RoundStarted);
+    }
+
+    if rules.topped {
+        for athlete in &current.ranking {
+            let a = &athlete.athlete;
+            if !favourites.contains(a.athlete_id, &a.country.country) {
+                continue;
+            }
+            let before = previous
+                .ranking
+                .iter()
+                .find(|b| b.athlete.athlete_id == a.athlete_id);
+            for (i, ascent) in athlete.ascents.iter().enumerate() {
+                let topped_before = before.and_then(|b| b.ascents.get(i)).map_or(false, topped);
+                if topped(ascent) && !topped_before {
+                    notifications.push(Notification::Topped {
+                        athlete: format!("{} {}", a.firstname, a.lastname),
+                        route: i + 1,
+                    });
+                }
+            }
+        }
+    }
+
+    if rules.finals_spot && current.round.to_lowercase().starts_with("semi") {
+        let before = ranks(previous);
+        for (id, name, country, rank) in ranks(current) {
+            if !favourites.contains(id, &country) || rank > FINALS_SPOTS {
+                continue;
+            }
+            let was_in_spot = before
+                .iter()
+                .any(|(b, _, _, r)| *b == id && *r <= FINALS_SPOTS);
+            if !was_in_spot {
+                notifications.push(Notification::FinalsSpot {
+                    athlete: name,
+                    rank,
+                });
+            }
+        }
+    }
+
+    if rules.round_finished && !finished(previous) && finished(current) {
+        notifications.push(Notification::RoundFinished);
+    }
+
+    notifications
+}
+
+fn topped(ascent: &result::Ascent) -> bool {
+    ascent.boulder.top == Some(true) || ascent.lead.score.as_deref() == Some("TOP")
+}
+
+fn ascents(results: &Results) -> impl Iterator<Item = &result::Ascent> {
+    results.ranking.iter().flat_map(|a| &a.ascents)
+}
+
+/// Whether any athlete is climbing or has climbed
+fn started(results: &Results) -> bool {
+    results.ranking.iter().any(|a| a.active)
+        || ascents(results).any(|a| a.status != Status::Pending)
+}
+
+/// Whether every ascent has been climbed and scored
+fn finished(results: &Results) -> bool {
+    started(results)
+        && !results.ranking.iter().any(|a| a.active)
+        && ascents(results).all(|a| a.status == Status::Confirmed || a.status == Status::Locked)
+}
+
+/// Our ranks with the id, name and country of every athlete
+fn ranks(results: &Results) -> Vec<(u64, String, String, usize)> {
+    match results.discipline {
+        DisciplineTag::Lead => ranks_of::<Lead>(results),
+        DisciplineTag::Boulder => ranks_of::<Boulder>(results),
+        DisciplineTag::Speed => ranks_of::<Speed>(results),
+        DisciplineTag::Unknown => ranks_of::<Unknown>(results),
+    }
+}
+
+fn ranks_of<D: Discipline>(results: &Results) -> Vec<(u64, String, String, usize)> {
+    ranking::rank::<D>(results)
+        .into_iter()
+        .map(|a| {
+            (
+                a.id,
+                format!("{} {}", a.first_name, a.last_name),
+                a.country,
+                a.rank,
+            )
+        })
+        .collect()
+}
+
+/// Show a notification of the operating system
+#[cfg(feature = "desktop")]
+pub fn notify(title: &str, body: &str) -> Result<(), String> {
+    notify_rust::Notification::new()
+        .summary(title)
+        .body(body)
+        .show()
+        .map(|_| ())
+        .map_err(|e| format!("Could not show a notification: {e:?}"))
+}
+
+/// Show a notification with the Notification API of the browser
+#[cfg(feature = "web")]
+pub fn notify(title: &str, body: &str) -> Result<(), String> {
+    use web_sys::{Notification, NotificationOptions, NotificationPermission};
+    if Notification::permission() != NotificationPermission::Granted {
+        return Ok(());
+    }
+    let mut options = NotificationOptions::new();
+    options.body(body);
+    Notification::new_with_options(title, &options)
+        .map(|_| ())
+        .map_err(|e| format!("Could not show a notification: {e:?}"))
+}
+
+/// Ask the browser for permission to show notifications, which has to happen
+/// in response to the user doing something.
+#[cfg(feature = "web")]
+pub fn request_permission() {
+    let _ = web_sys::Notification::request_permission();
+}
+
+#[cfg(feature = "desktop")]
+pub fn request_permission() {}
+
+/// Checkboxes for the notification rules, which are shared with the
+/// leaderboard
+pub fn NotificationRules(cx: Scope) -> Element {
+    let rules = use_shared_state::<Rules>(cx).unwrap();
+    let save_error = use_shared_state::<Option<SaveError>>(cx).unwrap();
+
+    let checkbox = |label: &'static str, get: fn(&Rules) -> bool, set: fn(&mut Rules, bool)| {
+        let checked = get(&rules.read());
+        rsx! {
+            label {
+                input {
+                    r#type: "checkbox",
+                    checked: "{checked}",
+                    onchange: move |_| {
+                        let mut new = rules.read().clone();
+                        set(&mut new, !checked);
+                        if !checked {
+                            request_permission();
+                        }
+                        *save_error.write() = new.save().err();
+                        *rules.write() = new;
+                    },
+                }
+                "{label}"
+            }
+        }
+    };
+
+    cx.render(rsx! {
+        details {
+            class: "notification-rules",
+            summary { "Notifications" }
+            checkbox("A favourite tops a route", |r| r.topped, |r, v| r.topped = v)
+            checkbox("A favourite moves into a finals spot", |r| r.finals_spot, |r, v| r.finals_spot = v)
+            checkbox("The round starts", |r| r.round_started, |r, v| r.round_started = v)
+            checkbox("The round finishes", |r| r.round_finished, |r, v| r.round_finished = v)
+        }
+    })
+}
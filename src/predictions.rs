@@ -0,0 +1,441 @@
+//! A prediction game: before a round starts, predictors pick the athletes they
+//! think will finish in the top places. After the round, every prediction is
+//! scored against the official ranking of the round, so athletes that tie
+//! share a place.
+//!
+//! A predicted athlete on the right place scores [`EXACT_POINTS`], one that
+//! finished in the top places but on another place scores [`TOP_POINTS`].
+use crate::api::{
+    self,
+    result::{Athlete, Results},
+    seasons::{CategoryRound, Event, RoundStatus, ShortEvent},
+};
+use crate::ranking;
+use crate::storage::{self, SaveError};
+use chrono::{DateTime, Utc};
+use dioxus::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+const STORAGE_KEY: &str = "predictions";
+
+/// The number of places that are predicted
+pub const TOP: usize = 3;
+
+pub const EXACT_POINTS: u64 = 3;
+pub const TOP_POINTS: u64 = 1;
+
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct Predictions {
+    pub predictions: Vec<Prediction>,
+}
+
+/// The predicted top places of a round by a predictor
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub struct Prediction {
+    pub predictor: String,
+    pub event_id: u64,
+    pub round_id: u64,
+    /// Like `IFSC World Cup Innsbruck 2023 - BOULDER Women Final`
+    pub round: String,
+    /// The athletes from the first place down
+    pub athletes: Vec<PredictedAthlete>,
+    pub submitted_at: DateTime<Utc>,
+}
+
+/// We keep the name of a predicted athlete, so that we can show predictions
+/// without fetching anything.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub struct PredictedAthlete {
+    pub athlete_id: u64,
+    pub firstname: String,
+    pub lastname: String,
+}
+
+impl From<&Athlete> for PredictedAthlete {
+    fn from(a: &Athlete) -> Self {
+        Self {
+            athlete_id: a.athlete_id,
+            firstname: a.firstname.clone(),
+            lastname: a.lastname.clone(),
+        }
+    }
+}
+
+/// Why a prediction was not accepted
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SubmitError {
+    NoPredictor,
+    NoAthletes,
+    SameAthleteTwice,
+    Started,
+}
+
+impl Display for SubmitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NoPredictor => f.write_str("Enter your name"),
+            Self::NoAthletes => f.write_str("Pick at least one athlete"),
+            Self::SameAthleteTwice => f.write_str("An athlete can only be picked once"),
+            Self::Started => f.write_str("The round has already started"),
+        }
+    }
+}
+
+/// The score of a prediction
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct Score {
+    pub points: u64,
+    /// Athletes on the right place
+    pub exact: usize,
+}
+
+/// The total of a predictor over all scored rounds
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Standing {
+    pub rank: u64,
+    pub predictor: String,
+    pub rounds: usize,
+    pub points: u64,
+    pub exact: usize,
+}
+
+impl Predictions {
+    pub fn load() -> Self {
+        storage::load(STORAGE_KEY).unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<(), SaveError> {
+        storage::save(STORAGE_KEY, self)
+    }
+
+    /// Add a prediction for a round that hasn't started yet. An earlier
+    /// prediction of the same predictor for the round is replaced.
+    pub fn submit(
+        &mut self,
+        prediction: Prediction,
+        round: &CategoryRound,
+        event_ends_at: DateTime<Utc>,
+        now: DateTime<Utc>,
+    ) -> Result<(), SubmitError> {
+        if prediction.predictor.trim().is_empty() {
+            return Err(SubmitError::NoPredictor);
+        }
+        if prediction.athletes.is_empty() {
+            return Err(SubmitError::NoAthletes);
+        }
+        let ids = &prediction.athletes;
+        if ids
+            .iter()
+            .enumerate()
+            .any(|(i, a)| ids[..i].iter().any(|b| b.athlete_id == a.athlete_id))
+        {
+            return Err(SubmitError::SameAthleteTwice);
+        }
+        // The status might be outdated, so the start time is checked as well
+        let started = round.starts_at.is_some_and(|start| start <= now);
+        if started || round.round_status(event_ends_at, now) != RoundStatus::Upcoming {
+            return Err(SubmitError::Started);
+        }
+
+        self.predictions
+            .retain(|p| p.round_id != prediction.round_id || p.predictor != prediction.predictor);
+        self.predictions.push(prediction);
+        Ok(())
+    }
+
+    /// The rounds with predictions, without duplicates
+    pub fn rounds(&self) -> Vec<(u64, u64)> {
+        let mut rounds: Vec<(u64, u64)> = Vec::new();
+        for p in &self.predictions {
+            if !rounds.contains(&(p.event_id, p.round_id)) {
+                rounds.push((p.event_id, p.round_id));
+            }
+        }
+        rounds
+    }
+}
+
+/// Score the predicted athletes against the official ranks of the round, by
+/// athlete id. Our own ranks can't be used, because they break ties and don't
+/// know the knockout heats of speed finals.
+pub fn score(prediction: &Prediction, ranks: &[(u64, u64)]) -> Score {
+    let places = prediction.athletes.len() as u64;
+    let mut score = Score::default();
+    for (i, athlete) in prediction.athletes.iter().enumerate() {
+        let Some(&(_, rank)) = ranks.iter().find(|(id, _)| *id == athlete.athlete_id) else {
+            continue;
+        };
+        if rank == i as u64 + 1 {
+            score.points += EXACT_POINTS;
+            score.exact += 1;
+        } else if rank <= places {
+            score.points += TOP_POINTS;
+        }
+    }
+    score
+}
+
+/// The standings of the predictors over the finished rounds, with the results
+/// of every finished round by round id. Predictions of other rounds are left
+/// out.
+pub fn leaderboard(predictions: &[Prediction], finished: &[(u64, Results)]) -> Vec<Standing> {
+    let mut standings: Vec<Standing> = Vec::new();
+    for p in predictions {
+        let Some((_, results)) = finished.iter().find(|(id, _)| *id == p.round_id) else {
+            continue;
+        };
+        let score = score(p, &ranking::official_ranks(results));
+        let index = match standings.iter().position(|s| s.predictor == p.predictor) {
+            Some(index) => index,
+            None => {
+                standings.push(Standing {
+                    rank: 0,
+                    predictor: p.predictor.clone(),
+                    rounds: 0,
+                    points: 0,
+                    exact: 0,
+                });
+                standings.len() - 1
+            }
+        };
+        let s = &mut standings[index];
+        s.rounds += 1;
+        s.points += score.points;
+        s.exact += score.exact;
+    }
+
+    // More exact places break ties in points
+    standings.sort_by(|a, b| {
+        (b.points, b.exact)
+            .cmp(&(a.points, a.exact))
+            .then(a.predictor.cmp(&b.predictor))
+    });
+    let keys: Vec<_> = standings.iter().map(|s| (s.points, s.exact)).collect();
+    for (s, key) in standings.iter_mut().zip(&keys) {
+        s.rank = 1 + keys.iter().filter(|k| *k > key).count() as u64;
+    }
+    standings
+}
+
+/// The current state of a round, which might have started since the page with
+/// the round was opened
+pub async fn fetch_round(event_id: u64, round_id: u64) -> Option<CategoryRound> {
+    let event = api::request::<Event>(format!("events/{event_id}")).await?;
+    event
+        .dcats
+        .into_iter()
+        .flat_map(|c| c.category_rounds)
+        .find(|r| r.category_round_id == round_id)
+}
+
+/// The results of the rounds with predictions that are finished
+pub async fn fetch_finished(predictions: &Predictions) -> Vec<(u64, Results)> {
+    let now = chrono::offset::Utc::now();
+    let mut finished = Vec::new();
+    let mut events: Vec<Event> = Vec::new();
+    for (event_id, round_id) in predictions.rounds() {
+        if !events.iter().any(|e| e.id == event_id) {
+            let Some(event) = api::request::<Event>(format!("events/{event_id}")).await else {
+                continue;
+            };
+            events.push(event);
+        }
+        let round = events
+            .iter()
+            .filter(|e| e.id == event_id)
+            .flat_map(|e| e.dcats.iter().map(move |c| (e, c)))
+            .flat_map(|(e, c)| c.category_rounds.iter().map(move |r| (e, r)))
+            .find(|(_, r)| r.category_round_id == round_id);
+        if round.map(|(e, r)| r.round_status(e.ends_at, now)) != Some(RoundStatus::Finished) {
+            continue;
+        }
+        let url = format!("category_rounds/{round_id}/results");
+        if let Some(results) = api::request::<Results>(url).await {
+            finished.push((round_id, results));
+        }
+    }
+    finished
+}
+
+#[derive(PartialEq, Props)]
+pub struct PredictionFormProps {
+    event: ShortEvent,
+    /// Like `BOULDER Women`
+    category: String,
+    round: CategoryRound,
+    /// The athletes that can be picked
+    athletes: Vec<Athlete>,
+}
+
+/// Pick the top places of an upcoming round
+pub fn PredictionForm(cx: Scope<PredictionFormProps>) -> Element {
+    let predictor = use_state(cx, String::new);
+    let picks = use_state(cx, || vec![None::<u64>; TOP]);
+    let status = use_state(cx, || None::<String>);
+
+    let selects = (0..TOP).map(|place| {
+        let options = cx.props.athletes.iter().map(|a| {
+            rsx! { option { value: "{a.athlete_id}", "{a.firstname} {a.lastname} ({a.country.country})" } }
+        });
+        let number = place + 1;
+        rsx! {
+            label {
+                "{number}. "
+                select {
+                    oninput: move |e| {
+                        let mut new = picks.get().clone();
+                        new[place] = e.value.parse().ok();
+                        picks.set(new);
+                    },
+                    option { value: "", "-" }
+                    options
+                }
+            }
+        }
+    });
+
+    let submit = move |_| {
+        let athletes = picks
+            .iter()
+            .flatten()
+            .filter_map(|id| cx.props.athletes.iter().find(|a| a.athlete_id == *id))
+            .map(PredictedAthlete::from)
+            .collect();
+        let prediction = Prediction {
+            predictor: predictor.trim().to_string(),
+            event_id: cx.props.event.event_id,
+            round_id: cx.props.round.category_round_id,
+            round: format!(
+                "{} - {} {}",
+                cx.props.event.event, cx.props.category, cx.props.round.name
+            ),
+            athletes,
+            submitted_at: chrono::offset::Utc::now(),
+        };
+        let status = status.to_owned();
+        let known = cx.props.round.clone();
+        let ends_at = cx.props.event.ends_at;
+        cx.spawn(async move {
+            let round = fetch_round(prediction.event_id, prediction.round_id)
+                .await
+                .unwrap_or(known);
+            let mut predictions = Predictions::load();
+            let now = chrono::offset::Utc::now();
+            let submitted = predictions.submit(prediction, &round, ends_at, now);
+            status.set(Some(match submitted {
+                Ok(()) => match predictions.save() {
+                    Ok(()) => "Your prediction is saved".to_string(),
+                    Err(e) => e.to_string(),
+                },
+                Err(e) => e.to_string(),
+            }));
+        });
+    };
+
+    cx.render(rsx! {
+        h2 { "Predict the {cx.props.round.name}" }
+        div {
+            class: "form prediction",
+            input {
+                placeholder: "Your name",
+                value: "{predictor}",
+                oninput: move |e| predictor.set(e.value.clone()),
+            }
+            selects
+            button { onclick: submit, "Submit" }
+        }
+        if let Some(status) = status.get() {
+            rsx! { div { class: "hint", "{status}" } }
+        }
+    })
+}
+
+/// The standings of the predictors and every prediction with its score
+pub fn PredictionGame(cx: Scope) -> Element {
+    let predictions = use_state(cx, Predictions::load);
+    let future = use_future(cx, (), |_| {
+        let predictions = predictions.get().clone();
+        async move { fetch_finished(&predictions).await }
+    });
+
+    let Some(finished) = future.value() else {
+        return cx.render(rsx! { "Loading..." });
+    };
+
+    let standings = leaderboard(&predictions.predictions, finished);
+    let standings = standings.iter().map(|s| {
+        rsx! {
+            tr {
+                key: "{s.predictor}",
+                td { "{s.rank}" }
+                td { "{s.predictor}" }
+                td { "{s.rounds}" }
+                td { "{s.exact}" }
+                td { class: "total", "{s.points}" }
+            }
+        }
+    });
+
+    let mut sorted: Vec<_> = predictions.predictions.iter().collect();
+    sorted.sort_by_key(|p| std::cmp::Reverse(p.submitted_at));
+    let rows = sorted.into_iter().map(|p| {
+        let picked = p
+            .athletes
+            .iter()
+            .map(|a| a.lastname.clone())
+            .collect::<Vec<_>>()
+            .join(", ");
+        let points = match finished.iter().find(|(id, _)| *id == p.round_id) {
+            Some((_, results)) => score(p, &ranking::official_ranks(results))
+                .points
+                .to_string(),
+            None => "Not finished".to_string(),
+        };
+        rsx! {
+            tr {
+                td { "{p.predictor}" }
+                td { "{p.round}" }
+                td { "{picked}" }
+                td { "{points}" }
+            }
+        }
+    });
+
+    cx.render(rsx! {
+        h1 { "Prediction game" }
+        div {
+            class: "hint",
+            "Predict the top {TOP} from the page of a category at an event. Every athlete on the right place scores {EXACT_POINTS} points, every other athlete in the top {TOP} scores {TOP_POINTS}."
+        }
+        if predictions.predictions.is_empty() {
+            rsx! { div { class: "hint", "There are no predictions yet" } }
+        }
+        table {
+            class: "standings",
+            thead {
+                tr {
+                    th { "Rank" }
+                    th { "Predictor" }
+                    th { "Rounds" }
+                    th { "Exact" }
+                    th { "Points" }
+                }
+            }
+            tbody { standings }
+        }
+        h2 { "Predictions" }
+        table {
+            class: "standings",
+            thead {
+                tr {
+                    th { "Predictor" }
+                    th { "Round" }
+                    th { "Top {TOP}" }
+                    th { "Points" }
+                }
+            }
+            tbody { rows }
+        }
+    })
+}
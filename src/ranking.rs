@@ -0,0 +1,202 @@
+//! Computing the ranking of a round, independent of how it is displayed
+use crate::api::result::{DisciplineTag, Results, RouteStartOrder};
+use crate::discipline::{Boulder, Discipline, Lead, Score, Speed, Unknown};
+use crate::storage::{self, SaveError};
+use chrono::{DateTime, Utc};
+use std::fmt::Display;
+
+const LOG_KEY: &str = "disagreements";
+
+/// The number of disagreements that are kept in the log, the oldest are
+/// dropped first
+const LOG_LIMIT: usize = 1000;
+
+pub struct RankedAthlete<D: Discipline> {
+    pub id: u64,
+    pub first_name: String,
+    pub last_name: String,
+    pub country: String,
+    pub flag: String,
+    /// One ascent for every route, `None` if it could not be read
+    pub ascents: Vec<Option<D::Ascent>>,
+    pub score: D::Score,
+    pub active: bool,
+    pub rank: usize,
+    /// Qualifications might be split into groups that are ranked separately
+    pub group: Option<String>,
+    /// The rank given by the API, within the group if there is one
+    pub official_rank: Option<u64>,
+    /// Our rank computed like the official rank: within the group and with
+    /// equal ranks for equal scores
+    pub comparable_rank: usize,
+    /// Ascents that could not be read, these are left out of the score
+    pub problems: Vec<String>,
+    /// Like `B1`, only for paraclimbing
+    pub sport_class: Option<String>,
+    pub sport_class_state: Option<String>,
+    pub start_orders: Vec<RouteStartOrder>,
+}
+
+impl<D: Discipline> RankedAthlete<D> {
+    /// Whether our rank differs from the official rank
+    pub fn disagrees(&self) -> bool {
+        D::Score::COMPARABLE
+            && self
+                .official_rank
+                .map_or(false, |r| r != self.comparable_rank as u64)
+    }
+}
+
+/// An athlete for which our rank differs from the official rank
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Disagreement {
+    pub athlete_id: u64,
+    pub athlete: String,
+    pub ours: usize,
+    pub official: u64,
+}
+
+impl Display for Disagreement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} ({}): ranked {} by us, {} officially",
+            self.athlete, self.athlete_id, self.ours, self.official
+        )
+    }
+}
+
+pub fn extract_athletes<D: Discipline>(results: &Results) -> Vec<RankedAthlete<D>> {
+    results
+        .ranking
+        .iter()
+        .map(|rank_athlete| {
+            let mut problems = Vec::new();
+            let ascents = rank_athlete
+                .ascents
+                .iter()
+                .enumerate()
+                .map(|(i, a)| match D::Ascent::try_from(a.clone()) {
+                    Ok(ascent) => Some(ascent),
+                    Err(e) => {
+                        problems.push(format!("Route {}: {e}", i + 1));
+                        None
+                    }
+                })
+                .collect::<Vec<_>>();
+            // FIXME: Get the start order from the start list. The start order field on the RankAthlete might be
+            // missing.
+            let score = D::Score::calculate(0, &ascents);
+            RankedAthlete {
+                id: rank_athlete.athlete.athlete_id,
+                first_name: rank_athlete.athlete.firstname.clone(),
+                last_name: rank_athlete.athlete.lastname.clone(),
+                ascents,
+                score,
+                active: rank_athlete.active,
+                country: rank_athlete.athlete.country.country.clone(),
+                flag: rank_athlete.athlete.country.flag_url.clone(),
+                // Rank is computed later by us, because the API uses weird unstable sorting
+                rank: 0,
+                group: rank_athlete.starting_group.clone(),
+                official_rank: match rank_athlete.starting_group {
+                    Some(_) => rank_athlete.group_rank,
+                    None => rank_athlete.rank,
+                },
+                comparable_rank: 0,
+                problems,
+                sport_class: rank_athlete.sport_class().map(String::from),
+                sport_class_state: rank_athlete.sport_class_state.clone(),
+                start_orders: rank_athlete.route_start_orders.clone(),
+            }
+        })
+        .collect()
+}
+
+/// Extract the athletes from the results and compute their ranks.
+///
+/// The athletes are returned in the order of the API, because the UI has to
+/// keep the nodes in the same order in the DOM. Use the `rank` field to sort
+/// them.
+pub fn rank<D: Discipline>(results: &Results) -> Vec<RankedAthlete<D>> {
+    let mut athletes = extract_athletes::<D>(results);
+
+    // Compare in reverse, because we want the highest score first.
+    let mut indices: Vec<_> = (0..athletes.len()).collect();
+    indices.sort_by(|&a, &b| athletes[b].score.cmp(&athletes[a].score));
+
+    for (rank, i) in indices.into_iter().enumerate() {
+        athletes[i].rank = rank + 1;
+    }
+
+    let comparable_ranks: Vec<_> = athletes
+        .iter()
+        .map(|a| {
+            1 + athletes
+                .iter()
+                .filter(|b| b.group == a.group && b.score > a.score)
+                .count()
+        })
+        .collect();
+    for (a, rank) in athletes.iter_mut().zip(comparable_ranks) {
+        a.comparable_rank = rank;
+    }
+
+    athletes
+}
+
+/// The athletes for which our rank differs from the official rank
+pub fn disagreements(results: &Results) -> Vec<Disagreement> {
+    match results.discipline {
+        DisciplineTag::Lead => disagreements_of::<Lead>(results),
+        DisciplineTag::Boulder => disagreements_of::<Boulder>(results),
+        DisciplineTag::Speed => disagreements_of::<Speed>(results),
+        DisciplineTag::Unknown => disagreements_of::<Unknown>(results),
+    }
+}
+
+fn disagreements_of<D: Discipline>(results: &Results) -> Vec<Disagreement> {
+    rank::<D>(results)
+        .into_iter()
+        .filter(|a| a.disagrees())
+        .map(|a| Disagreement {
+            athlete_id: a.id,
+            athlete: format!("{} {}", a.first_name, a.last_name),
+            ours: a.comparable_rank,
+            official: a.official_rank.unwrap_or_default(),
+        })
+        .collect()
+}
+
+/// The disagreements found in earlier rounds, oldest first
+pub fn disagreement_log() -> Vec<String> {
+    storage::load(LOG_KEY).unwrap_or_default()
+}
+
+/// Add disagreements in a round to the log. The log is kept in the storage,
+/// so that the scoring bugs found while following a live round aren't lost.
+pub fn log_disagreements(
+    round: &str,
+    disagreements: &[Disagreement],
+    at: DateTime<Utc>,
+) -> Result<(), SaveError> {
+    if disagreements.is_empty() {
+        return Ok(());
+    }
+    let at = at.format("%Y-%m-%d %H:%M:%S");
+    let mut log = disagreement_log();
+    log.extend(disagreements.iter().map(|d| format!("{at} {round}: {d}")));
+    let excess = log.len().saturating_sub(LOG_LIMIT);
+    log.drain(..excess);
+    storage::save(LOG_KEY, &log)
+}
+
+/// The official rank of every ranked athlete by athlete id, in the order of
+/// the API. Athletes with the same result share their rank.
+pub fn official_ranks(results: &Results) -> Vec<(u64, u64)> {
+    results
+        .ranking
+        .iter()
+        .filter_map(|a| Some((a.athlete.athlete_id, a.rank?)))
+        .collect()
+}
@@ -0,0 +1,350 @@
+//! Elo ratings of the athletes in every discipline, computed from the official
+//! ranks of the rounds they climbed in.
+//!
+//! A round is treated as a match between every pair of ranked athletes in it:
+//! the higher ranked athlete wins, athletes with the same rank draw. The
+//! change of a rating is scaled by the number of opponents, so a qualification
+//! with 100 athletes doesn't count 100 times as much as a final with 8.
+//!
+//! The rounds come from the local database filled by the sync command, so the
+//! ratings are only available in the desktop app. They are computed once in
+//! the background when the app starts and shared by the pages that show them;
+//! the web version has no ratings.
+use crate::api::{
+    self,
+    athlete::AthleteProfile,
+    result::{Athlete, DisciplineTag, Results},
+};
+use chrono::{DateTime, Utc};
+use dioxus::prelude::*;
+use std::collections::HashMap;
+
+/// The rating of an athlete before their first round
+pub const INITIAL: f64 = 1500.0;
+
+/// How much a single round can change a rating
+const K: f64 = 32.0;
+
+/// The rating of an athlete after a round
+#[derive(Clone, Debug, PartialEq)]
+pub struct RatingPoint {
+    pub at: DateTime<Utc>,
+    pub event: String,
+    pub rating: f64,
+}
+
+/// The ratings of an athlete in a discipline over time
+#[derive(Clone, Debug, PartialEq)]
+pub struct Track {
+    pub discipline: DisciplineTag,
+    pub athlete: Athlete,
+    pub points: Vec<RatingPoint>,
+}
+
+impl Track {
+    pub fn current(&self) -> f64 {
+        self.points.last().map_or(INITIAL, |p| p.rating)
+    }
+
+    /// The change of the rating over the last rounds
+    pub fn trend(&self, rounds: usize) -> f64 {
+        let before = match self.points.len().checked_sub(rounds + 1) {
+            Some(i) => self.points[i].rating,
+            None => INITIAL,
+        };
+        self.current() - before
+    }
+}
+
+/// An athlete in an upcoming round with the rank predicted by the ratings
+#[derive(Clone, Debug, PartialEq)]
+pub struct Prediction {
+    pub rank: u64,
+    pub athlete: Athlete,
+    pub rating: f64,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Ratings {
+    tracks: HashMap<(DisciplineTag, u64), Track>,
+}
+
+/// The expected score of an athlete with rating `a` against one with rating
+/// `b`, between 0 and 1
+pub fn expected(a: f64, b: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf((b - a) / 400.0))
+}
+
+impl Ratings {
+    /// Update the ratings with a round. The rounds have to be added in the
+    /// order in which they were climbed.
+    pub fn add_round(&mut self, at: DateTime<Utc>, results: &Results) {
+        let ranked: Vec<_> = results
+            .ranking
+            .iter()
+            .filter_map(|a| Some((&a.athlete, a.rank?)))
+            .collect();
+        if ranked.len() < 2 {
+            return;
+        }
+
+        // All ratings are updated at once, so the order of the athletes in the
+        // round doesn't matter
+        let discipline = &results.discipline;
+        let before: Vec<f64> = ranked
+            .iter()
+            .map(|(a, _)| self.rating(discipline, a.athlete_id))
+            .collect();
+        let opponents = (ranked.len() - 1) as f64;
+        for (i, (athlete, rank)) in ranked.iter().enumerate() {
+            let mut score = 0.0;
+            let mut expected_score = 0.0;
+            for (j, (_, other)) in ranked.iter().enumerate() {
+                if i == j {
+                    continue;
+                }
+                score += match rank.cmp(other) {
+                    std::cmp::Ordering::Less => 1.0,
+                    std::cmp::Ordering::Equal => 0.5,
+                    std::cmp::Ordering::Greater => 0.0,
+                };
+                expected_score += expected(before[i], before[j]);
+            }
+            let rating = before[i] + K * (score - expected_score) / opponents;
+
+            let track = self
+                .tracks
+                .entry((discipline.clone(), athlete.athlete_id))
+                .or_insert_with(|| Track {
+                    discipline: discipline.clone(),
+                    athlete: (*athlete).clone(),
+                    points: Vec::new(),
+                });
+            track.points.push(RatingPoint {
+                at,
+                event: results.event.clone().unwrap_or_default(),
+                rating,
+            });
+        }
+    }
+
+    /// The current rating, which is the initial rating for athletes without
+    /// rounds in the discipline
+    pub fn rating(&self, discipline: &DisciplineTag, athlete_id: u64) -> f64 {
+        self.track(discipline, athlete_id)
+            .map_or(INITIAL, Track::current)
+    }
+
+    pub fn track(&self, discipline: &DisciplineTag, athlete_id: u64) -> Option<&Track> {
+        self.tracks.get(&(discipline.clone(), athlete_id))
+    }
+
+    /// The tracks of an athlete in every discipline they climbed in
+    pub fn athlete(&self, athlete_id: u64) -> Vec<&Track> {
+        let mut tracks: Vec<_> = self
+            .tracks
+            .values()
+            .filter(|t| t.athlete.athlete_id == athlete_id)
+            .collect();
+        tracks.sort_by_key(|t| t.discipline.to_string());
+        tracks
+    }
+
+    /// The best rated athletes of a discipline, best first
+    pub fn top(&self, discipline: &DisciplineTag, limit: usize) -> Vec<&Track> {
+        let mut tracks: Vec<_> = self
+            .tracks
+            .values()
+            .filter(|t| &t.discipline == discipline)
+            .collect();
+        tracks.sort_by(|a, b| {
+            b.current()
+                .total_cmp(&a.current())
+                .then(a.athlete.athlete_id.cmp(&b.athlete.athlete_id))
+        });
+        tracks.truncate(limit);
+        tracks
+    }
+
+    /// The place of the athlete among all rated athletes of the discipline
+    pub fn position(&self, discipline: &DisciplineTag, athlete_id: u64) -> Option<usize> {
+        self.top(discipline, usize::MAX)
+            .iter()
+            .position(|t| t.athlete.athlete_id == athlete_id)
+            .map(|i| i + 1)
+    }
+
+    /// The predicted ranking of the athletes in a round, by their ratings.
+    /// Athletes with the same rating get the same rank.
+    pub fn predict(&self, discipline: &DisciplineTag, athletes: &[Athlete]) -> Vec<Prediction> {
+        let mut rated: Vec<_> = athletes
+            .iter()
+            .map(|a| (a, self.rating(discipline, a.athlete_id)))
+            .collect();
+        rated.sort_by(|(a, x), (b, y)| y.total_cmp(x).then(a.athlete_id.cmp(&b.athlete_id)));
+
+        let mut predictions: Vec<Prediction> = Vec::new();
+        for (i, (athlete, rating)) in rated.into_iter().enumerate() {
+            let rank = match predictions.last() {
+                Some(p) if p.rating == rating => p.rank,
+                _ => i as u64 + 1,
+            };
+            predictions.push(Prediction {
+                rank,
+                athlete: athlete.clone(),
+                rating,
+            });
+        }
+        predictions
+    }
+}
+
+/// The ratings shared by every page, see [`load`]
+#[derive(Default)]
+pub enum SharedRatings {
+    #[default]
+    Loading,
+    Loaded(Option<Ratings>),
+}
+
+/// The ratings from the rounds stored in the local database, if there is one.
+/// Reading the database and replaying every round takes a while, so it
+/// happens on a blocking thread.
+#[cfg(feature = "desktop")]
+pub async fn load() -> Option<Ratings> {
+    tokio::task::spawn_blocking(|| {
+        let path = std::path::Path::new(crate::history::DEFAULT_PATH);
+        if !path.exists() {
+            return None;
+        }
+        crate::history::History::open(path).ok()?.ratings().ok()
+    })
+    .await
+    .ok()
+    .flatten()
+}
+
+/// The ratings need the local database, which the browser doesn't have
+#[cfg(not(feature = "desktop"))]
+pub async fn load() -> Option<Ratings> {
+    None
+}
+
+/// Why there are no ratings to show
+pub const NO_RATINGS: &str = "Ratings are computed from the results stored with the sync command of the desktop app, they are not available on the web";
+
+/// The points of a track as an SVG line, scaled to the width and height
+fn sparkline(points: &[RatingPoint], width: f64, height: f64) -> String {
+    let min = points
+        .iter()
+        .map(|p| p.rating)
+        .fold(f64::INFINITY, f64::min);
+    let max = points
+        .iter()
+        .map(|p| p.rating)
+        .fold(f64::NEG_INFINITY, f64::max);
+    let range = (max - min).max(1.0);
+    let step = width / (points.len().max(2) - 1) as f64;
+    let line = points
+        .iter()
+        .enumerate()
+        .map(|(i, p)| {
+            let x = i as f64 * step;
+            let y = height - (p.rating - min) / range * height;
+            format!("{x:.1},{y:.1}")
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+    format!(
+        r#"<svg width="{width}" height="{height}"><polyline points="{line}" fill="none" stroke="currentColor"/></svg>"#
+    )
+}
+
+#[derive(PartialEq, Props)]
+pub struct AthletePageProps {
+    id: u64,
+}
+
+/// An athlete with their current rating and its trend in every discipline
+pub fn AthletePage(cx: Scope<AthletePageProps>) -> Element {
+    let id = cx.props.id;
+    let url = format!("athletes/{id}");
+    let future = use_future(cx, (&id,), |_| api::request::<AthleteProfile>(url));
+    let ratings = use_shared_state::<SharedRatings>(cx).unwrap();
+
+    let name = match future.value() {
+        Some(Some(profile)) => format!(
+            "{} {} ({})",
+            profile.firstname, profile.lastname, profile.country
+        ),
+        Some(None) => return cx.render(rsx! { "Could not load the athlete" }),
+        None => return cx.render(rsx! { "Loading..." }),
+    };
+
+    let ratings = ratings.read();
+    let ratings = match &*ratings {
+        SharedRatings::Loaded(Some(ratings)) => ratings,
+        SharedRatings::Loaded(None) => {
+            return cx.render(rsx! {
+                h1 { "{name}" }
+                div { class: "hint", "{NO_RATINGS}" }
+            })
+        }
+        SharedRatings::Loading => {
+            return cx.render(rsx! {
+                h1 { "{name}" }
+                div { class: "hint", "Computing the ratings..." }
+            })
+        }
+    };
+
+    let tracks = ratings.athlete(id).into_iter().map(|t| {
+        let rating = t.current().round();
+        let trend = t.trend(5).round();
+        let trend = if trend > 0.0 {
+            format!("+{trend}")
+        } else {
+            trend.to_string()
+        };
+        let recent = &t.points[t.points.len().saturating_sub(20)..];
+        let chart = sparkline(recent, 200.0, 40.0);
+        let rounds = t.points.len();
+        let position = ratings
+            .position(&t.discipline, id)
+            .map_or(String::new(), |p| p.to_string());
+        let last = t.points.last().map_or(String::new(), |p| p.event.clone());
+        rsx! {
+            tr {
+                td { "{t.discipline}" }
+                td { class: "total", "{rating}" }
+                td { "{position}" }
+                td { title: "Over the last 5 rounds", "{trend}" }
+                td { "{rounds}" }
+                td { dangerous_inner_html: "{chart}" }
+                td { "{last}" }
+            }
+        }
+    });
+
+    cx.render(rsx! {
+        h1 { "{name}" }
+        if ratings.athlete(id).is_empty() {
+            rsx! { div { class: "hint", "There are no stored rounds of this athlete" } }
+        }
+        table {
+            class: "standings",
+            thead {
+                tr {
+                    th { "Discipline" }
+                    th { "Rating" }
+                    th { "Place" }
+                    th { "Trend" }
+                    th { "Rounds" }
+                    th { "Last 20 rounds" }
+                    th { "Last event" }
+                }
+            }
+            tbody { tracks }
+        }
+    })
+}
@@ -0,0 +1,248 @@
+//! Searching seasons, events and athletes by name, with fuzzy matching
+use crate::api::{
+    self,
+    result::Results,
+    seasons::{Season, SeasonsResponse, ShortEvent, ShortSeason},
+};
+use crate::leaderboard::LeaderboardInput;
+use crate::storage::{self, SaveError};
+use crate::Page;
+use dioxus::prelude::*;
+use serde::{Deserialize, Serialize};
+
+const STORAGE_KEY: &str = "athletes";
+
+/// The maximum number of search results that are shown
+const RESULTS: usize = 20;
+
+/// The maximum number of athletes that are remembered, the ones seen longest
+/// ago are forgotten first
+pub const SEEN_LIMIT: usize = 2000;
+
+/// An athlete we have seen in results, with the last round they were seen in
+#[derive(Clone, Serialize, Deserialize, PartialEq)]
+pub struct SeenAthlete {
+    pub athlete_id: u64,
+    pub firstname: String,
+    pub lastname: String,
+    pub country: String,
+    pub event: String,
+    pub round_id: u64,
+}
+
+pub fn seen_athletes() -> Vec<SeenAthlete> {
+    storage::load(STORAGE_KEY).unwrap_or_default()
+}
+
+/// Remember the athletes of a round, so that they can be found later. The
+/// store is only written if it changes.
+pub fn remember_athletes(event: &str, round_id: u64, results: &Results) -> Result<(), SaveError> {
+    let mut athletes = seen_athletes();
+    if !remember(&mut athletes, event, round_id, results) {
+        return Ok(());
+    }
+    storage::save(STORAGE_KEY, &athletes)
+}
+
+/// Add the athletes of a round to the seen athletes, or move them to the end
+/// if they were seen before, keeping at most [`SEEN_LIMIT`] of them. Returns
+/// whether anything changed.
+pub fn remember(
+    athletes: &mut Vec<SeenAthlete>,
+    event: &str,
+    round_id: u64,
+    results: &Results,
+) -> bool {
+    let mut changed = false;
+    for rank_athlete in &results.ranking {
+        let a = &rank_athlete.athlete;
+        let seen = SeenAthlete {
+            athlete_id: a.athlete_id,
+            firstname: a.firstname.clone(),
+            lastname: a.lastname.clone(),
+            country: a.country.country.clone(),
+            event: event.to_string(),
+            round_id,
+        };
+        if athletes.contains(&seen) {
+            continue;
+        }
+        athletes.retain(|s| s.athlete_id != a.athlete_id);
+        athletes.push(seen);
+        changed = true;
+    }
+    let excess = athletes.len().saturating_sub(SEEN_LIMIT);
+    athletes.drain(..excess);
+    changed
+}
+
+/// Something that can be found
+#[derive(Clone, PartialEq)]
+pub struct Entry {
+    /// What kind of thing this is, like "Event"
+    pub kind: &'static str,
+    pub title: String,
+    pub detail: String,
+    /// The page that is opened when the entry is chosen
+    pub target: Page,
+}
+
+#[derive(Clone, Default, PartialEq)]
+pub struct Index {
+    pub entries: Vec<Entry>,
+}
+
+impl Index {
+    pub fn add_seasons(&mut self, seasons: &[ShortSeason]) {
+        self.entries.extend(seasons.iter().map(|s| Entry {
+            kind: "Season",
+            title: s.name.clone(),
+            detail: String::new(),
+            target: Page::Season(s.clone()),
+        }));
+    }
+
+    pub fn add_events(&mut self, season: &str, events: &[ShortEvent]) {
+        self.entries.extend(events.iter().map(|e| Entry {
+            kind: "Event",
+            title: e.event.clone(),
+            detail: format!("{} {season}", e.location),
+            target: Page::Event(e.clone()),
+        }));
+    }
+
+    pub fn add_athletes(&mut self, athletes: &[SeenAthlete]) {
+        self.entries.extend(athletes.iter().map(|a| Entry {
+            kind: "Athlete",
+            title: format!("{} {}", a.firstname, a.lastname),
+            detail: format!("{} {}", a.country, a.event),
+            target: Page::Leaderboard(LeaderboardInput::Api(a.event.clone(), a.round_id)),
+        }));
+    }
+
+    /// The best matches for the query, best first
+    pub fn search(&self, query: &str, limit: usize) -> Vec<&Entry> {
+        if query.trim().is_empty() {
+            return Vec::new();
+        }
+        let mut matches: Vec<_> = self
+            .entries
+            .iter()
+            .filter_map(|e| {
+                // Matches in the title are worth more than in the details
+                let title = score(query, &e.title).map(|s| s * 2);
+                let all = score(query, &format!("{} {}", e.title, e.detail));
+                Some((title.max(all)?, e))
+            })
+            .collect();
+        matches.sort_by(|(a, _), (b, _)| b.cmp(a));
+        matches.into_iter().take(limit).map(|(_, e)| e).collect()
+    }
+}
+
+/// How well the query matches the text, or `None` if it doesn't match. Every
+/// word of the query has to match.
+pub fn score(query: &str, text: &str) -> Option<i64> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut total = 0;
+    for word in query.split_whitespace() {
+        let word: Vec<char> = word.to_lowercase().chars().collect();
+        total += fuzzy_score(&word, &text)?;
+    }
+    Some(total)
+}
+
+/// Match the characters of the word in order, but not necessarily next to each
+/// other, to the text. Consecutive characters and characters at the start of a
+/// word in the text score higher, skipped characters lower.
+fn fuzzy_score(word: &[char], text: &[char]) -> Option<i64> {
+    let first = *word.first()?;
+    (0..text.len())
+        .filter(|&start| text[start] == first)
+        .filter_map(|start| {
+            let mut score = 0;
+            let mut position = start;
+            let mut previous: Option<usize> = None;
+            for &c in word {
+                let found = position + text[position..].iter().position(|&t| t == c)?;
+                score += 1;
+                if found == 0 || !text[found - 1].is_alphanumeric() {
+                    score += 8;
+                }
+                match previous {
+                    Some(p) if p + 1 == found => score += 5,
+                    Some(p) => score -= (found - p - 1).min(10) as i64,
+                    None => {}
+                }
+                previous = Some(found);
+                position = found + 1;
+            }
+            Some(score)
+        })
+        .max()
+}
+
+pub fn Search(cx: Scope) -> Element {
+    let page = use_shared_state::<Page>(cx).unwrap();
+    let query = use_state(cx, String::new);
+    let index = use_state(cx, Index::default);
+    let status = use_state(cx, || Some("Loading the seasons...".to_string()));
+
+    // The seasons are indexed one by one, so that we can search while the
+    // older seasons are still loading.
+    let _ = use_coroutine(cx, |_: UnboundedReceiver<()>| {
+        let index = index.to_owned();
+        let status = status.to_owned();
+        async move {
+            let mut new = Index::default();
+            new.add_athletes(&seen_athletes());
+            index.set(new.clone());
+
+            let Some(SeasonsResponse { seasons }) =
+                api::request::<SeasonsResponse>(String::new()).await
+            else {
+                status.set(Some("Could not load the seasons".to_string()));
+                return;
+            };
+            new.add_seasons(&seasons);
+            index.set(new.clone());
+
+            for s in seasons {
+                status.set(Some(format!("Indexing {}...", s.name)));
+                if let Some(season) = api::request::<Season>(format!("seasons/{}", s.id)).await {
+                    new.add_events(&season.name, &season.events);
+                    index.set(new.clone());
+                }
+            }
+            status.set(None);
+        }
+    });
+
+    let results = index.search(query.get(), RESULTS).into_iter().map(|e| {
+        let target = e.target.clone();
+        rsx! {
+            div {
+                class: "event",
+                onclick: move |_| *page.write() = target.clone(),
+                div { "{e.title}" }
+                div { class: "datetime", "{e.kind} | {e.detail}" }
+            }
+        }
+    });
+
+    cx.render(rsx! {
+        div {
+            class: "form",
+            input {
+                placeholder: "Search seasons, events and athletes",
+                autofocus: true,
+                value: "{query}",
+                oninput: move |e| query.set(e.value.clone()),
+            }
+        }
+        if let Some(status) = status.get() {
+            rsx! { div { class: "hint", "{status}" } }
+        }
+        results
+    })
+}
@@ -0,0 +1,206 @@
+//! A local HTTP server that exposes the rankings as computed by us, for other
+//! tools like bots and stream graphics.
+//!
+//! - `GET /rounds/{id}` gives the current ranking of a category round
+//! - `GET /rounds/{id}/events` is a stream of server-sent events with the
+//!   ranking, sent whenever it changes
+use crate::api::result::{DisciplineTag, Results};
+use crate::discipline::{Boulder, Discipline, Lead, Score, Speed, Unknown};
+use crate::leaderboard::{fetch_results, poll_results, LeaderboardInput};
+use crate::ranking;
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    routing::get,
+    Json, Router,
+};
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, HashMap},
+    convert::Infallible,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+};
+use tokio::sync::watch;
+use tokio_stream::{wrappers::WatchStream, StreamExt};
+use tower_http::cors::CorsLayer;
+
+#[derive(Clone, PartialEq, Serialize)]
+struct Ranking {
+    discipline: String,
+    event: Option<String>,
+    category: String,
+    round: String,
+    athletes: Vec<RankingEntry>,
+}
+
+/// A ranked athlete, with the discipline specific parts as text
+#[derive(Clone, PartialEq, Serialize)]
+struct RankingEntry {
+    rank: usize,
+    athlete_id: u64,
+    firstname: String,
+    lastname: String,
+    country: String,
+    active: bool,
+    ascents: Vec<String>,
+    score: BTreeMap<&'static str, String>,
+    /// Ascents of the API that could not be read
+    problems: Vec<String>,
+}
+
+impl Ranking {
+    fn new(results: &Results) -> Self {
+        let athletes = match results.discipline {
+            DisciplineTag::Lead => entries::<Lead>(results),
+            DisciplineTag::Boulder => entries::<Boulder>(results),
+            DisciplineTag::Speed => entries::<Speed>(results),
+            DisciplineTag::Unknown => entries::<Unknown>(results),
+        };
+        Self {
+            discipline: results.discipline.to_string(),
+            event: results.event.clone(),
+            category: results.category.clone(),
+            round: results.round.clone(),
+            athletes,
+        }
+    }
+}
+
+fn entries<D: Discipline>(results: &Results) -> Vec<RankingEntry> {
+    let mut athletes = ranking::rank::<D>(results);
+    athletes.sort_by_key(|a| a.rank);
+    athletes
+        .into_iter()
+        .map(|a| RankingEntry {
+            rank: a.rank,
+            athlete_id: a.id,
+            firstname: a.first_name,
+            lastname: a.last_name,
+            country: a.country,
+            active: a.active,
+            ascents: a
+                .ascents
+                .iter()
+                .map(|x| x.as_ref().map(|x| x.to_string()).unwrap_or_default())
+                .collect(),
+            score: D::Score::FIELDS
+                .iter()
+                .copied()
+                .zip(a.score.values())
+                .collect(),
+            problems: a.problems,
+        })
+        .collect()
+}
+
+/// The rounds that have been requested, each of them is polled in the
+/// background as long as someone is subscribed to it.
+#[derive(Clone, Default)]
+pub(crate) struct Rounds(Arc<Mutex<HashMap<u64, watch::Receiver<Ranking>>>>);
+
+impl Rounds {
+    /// Subscribe to the ranking of a round, `None` if it could not be loaded
+    async fn subscribe(&self, id: u64) -> Option<watch::Receiver<Ranking>> {
+        let existing = self.0.lock().unwrap().get(&id).cloned();
+        if existing.is_some() {
+            return existing;
+        }
+
+        // Only rounds that exist are polled
+        let results = fetch(id).await?;
+        let mut rounds = self.0.lock().unwrap();
+        let rx = rounds.entry(id).or_insert_with(|| {
+            let (tx, rx) = watch::channel(Ranking::new(&results));
+            tokio::spawn(self.clone().poll(id, results, tx));
+            rx
+        });
+        Some(rx.clone())
+    }
+
+    #[cfg(test)]
+    pub(crate) fn is_polling(&self, id: u64) -> bool {
+        self.0.lock().unwrap().contains_key(&id)
+    }
+
+    /// Whether the round has subscribers besides the receiver in the map,
+    /// removing the round from the map otherwise
+    fn keep_polling(&self, id: u64, tx: &watch::Sender<Ranking>) -> bool {
+        let mut rounds = self.0.lock().unwrap();
+        if tx.receiver_count() > 1 {
+            return true;
+        }
+        rounds.remove(&id);
+        false
+    }
+
+    /// Poll the round like the leaderboard does, starting from the results of
+    /// the first request
+    async fn poll(self, id: u64, known: Results, tx: watch::Sender<Ranking>) {
+        let input = LeaderboardInput::Api(String::new(), id);
+        poll_results(
+            input,
+            Some(known),
+            || self.keep_polling(id, &tx),
+            |results| {
+                // A failed request keeps the last ranking
+                if let Some(results) = results {
+                    tx.send_replace(Ranking::new(&results));
+                }
+            },
+        )
+        .await
+    }
+}
+
+async fn fetch(id: u64) -> Option<Results> {
+    fetch_results(&LeaderboardInput::Api(String::new(), id)).await
+}
+
+fn bad_gateway() -> Response {
+    (
+        StatusCode::BAD_GATEWAY,
+        "Could not load the round from the API",
+    )
+        .into_response()
+}
+
+async fn round(State(rounds): State<Rounds>, Path(id): Path<u64>) -> Response {
+    match rounds.subscribe(id).await {
+        Some(rx) => Json(rx.borrow().clone()).into_response(),
+        None => bad_gateway(),
+    }
+}
+
+async fn round_events(State(rounds): State<Rounds>, Path(id): Path<u64>) -> Response {
+    let Some(rx) = rounds.subscribe(id).await else {
+        return bad_gateway();
+    };
+    let stream = WatchStream::new(rx)
+        .map(|ranking| Ok::<_, Infallible>(Event::default().json_data(ranking).unwrap()));
+    Sse::new(stream)
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+pub(crate) fn app(rounds: Rounds) -> Router {
+    Router::new()
+        .route("/rounds/:id", get(round))
+        .route("/rounds/:id/events", get(round_events))
+        .layer(CorsLayer::permissive())
+        .with_state(rounds)
+}
+
+pub async fn serve(port: u16) {
+    let app = app(Rounds::default());
+    let addr = SocketAddr::from(([127, 0, 0, 1], port));
+    println!("Listening on http://{addr}");
+    axum::Server::bind(&addr)
+        .serve(app.into_make_service())
+        .await
+        .unwrap();
+}
@@ -0,0 +1,116 @@
+//! Standings of the countries: the medal table and the national team ranking
+use super::{points, EventResult};
+
+/// The number of best athletes of a country per category and event that count
+/// for the national team ranking
+pub const TEAM_ATHLETES: usize = 3;
+
+/// The medals of a country
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MedalCount {
+    pub country: String,
+    pub rank: usize,
+    pub gold: u64,
+    pub silver: u64,
+    pub bronze: u64,
+}
+
+impl MedalCount {
+    pub fn total(&self) -> u64 {
+        self.gold + self.silver + self.bronze
+    }
+}
+
+/// A country in the national team ranking
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TeamStanding {
+    pub country: String,
+    pub rank: usize,
+    pub points: u64,
+}
+
+/// Count the medals per country, ranked by gold, then silver, then bronze
+/// medals. Athletes that share a place all get the medal.
+pub fn medal_table<'a>(results: impl IntoIterator<Item = &'a EventResult>) -> Vec<MedalCount> {
+    let mut table: Vec<MedalCount> = Vec::new();
+    for placing in results.into_iter().flat_map(|r| &r.placings) {
+        if !(1..=3).contains(&placing.rank) {
+            continue;
+        }
+        let country = &placing.athlete.country.country;
+        let index = match table.iter().position(|m| &m.country == country) {
+            Some(index) => index,
+            None => {
+                table.push(MedalCount {
+                    country: country.clone(),
+                    rank: 0,
+                    gold: 0,
+                    silver: 0,
+                    bronze: 0,
+                });
+                table.len() - 1
+            }
+        };
+        let medals = &mut table[index];
+        match placing.rank {
+            1 => medals.gold += 1,
+            2 => medals.silver += 1,
+            _ => medals.bronze += 1,
+        }
+    }
+
+    let key = |m: &MedalCount| (m.gold, m.silver, m.bronze);
+    let keys: Vec<_> = table.iter().map(key).collect();
+    for medals in &mut table {
+        medals.rank = 1 + keys.iter().filter(|&&k| k > key(medals)).count();
+    }
+    table.sort_by(|a, b| a.rank.cmp(&b.rank).then_with(|| a.country.cmp(&b.country)));
+    table
+}
+
+/// Rank the countries by the World Cup points of their best
+/// [`TEAM_ATHLETES`] athletes in every category of every event.
+pub fn team_ranking<'a>(results: impl IntoIterator<Item = &'a EventResult>) -> Vec<TeamStanding> {
+    let mut ranking: Vec<TeamStanding> = Vec::new();
+    for result in results {
+        // The placings are sorted by rank, so the first athletes of a country
+        // are its best ones.
+        let mut counted: Vec<(&str, usize)> = Vec::new();
+        for placing in &result.placings {
+            let country = placing.athlete.country.country.as_str();
+            let index = match counted.iter().position(|(c, _)| *c == country) {
+                Some(index) => index,
+                None => {
+                    counted.push((country, 0));
+                    counted.len() - 1
+                }
+            };
+            if counted[index].1 == TEAM_ATHLETES {
+                continue;
+            }
+            counted[index].1 += 1;
+
+            let tied = result
+                .placings
+                .iter()
+                .filter(|p| p.rank == placing.rank)
+                .count() as u64;
+            let points = points(placing.rank, tied);
+            match ranking.iter_mut().find(|t| t.country == country) {
+                Some(team) => team.points += points,
+                None => ranking.push(TeamStanding {
+                    country: country.to_string(),
+                    rank: 0,
+                    points,
+                }),
+            }
+        }
+    }
+
+    let totals: Vec<_> = ranking.iter().map(|t| t.points).collect();
+    for team in &mut ranking {
+        team.rank = 1 + totals.iter().filter(|&&t| t > team.points).count();
+    }
+    ranking.sort_by(|a, b| a.rank.cmp(&b.rank).then_with(|| a.country.cmp(&b.country)));
+    ranking
+}
@@ -0,0 +1,282 @@
+//! The season-wide World Cup ranking, aggregated from the final results of
+//! every event of a season.
+//!
+//! Every athlete gets points for their place at an event according to the
+//! World Cup points table, and the overall ranking is the sum of these points,
+//! optionally counting only the best results of every athlete.
+use crate::api::{
+    self,
+    result::{Athlete, Results},
+    seasons::{CategoryRound, Event, Season, ShortCategory, ShortEvent},
+};
+
+mod countries;
+mod view;
+
+pub use countries::{medal_table, team_ranking, MedalCount, TeamStanding, TEAM_ATHLETES};
+pub use view::{Countries, CountryScope, EventCategory, Standings};
+
+/// The World Cup points for the places 1 to 30, places below that get no
+/// points.
+pub const POINTS: [u64; 30] = [
+    1000, 805, 690, 610, 545, 495, 455, 415, 380, 350, 325, 300, 280, 260, 240, 220, 205, 185, 170,
+    155, 145, 130, 120, 105, 90, 75, 65, 55, 47, 40,
+];
+
+/// The league of the World Cups, which they share with the World
+/// Championships
+pub const WORLD_CUP_LEAGUE: &str = "World Cups and World Championships";
+
+/// Whether an event counts for the World Cup ranking: the World Cups, but not
+/// the World Championships in their league or the events of the youth and
+/// paraclimbing leagues.
+pub fn is_world_cup(league: &str, event: &str) -> bool {
+    league == WORLD_CUP_LEAGUE && !event.contains("Championship")
+}
+
+/// The points for a place shared by `tied` athletes. Tied athletes share the
+/// points of the places they occupy, rounded down.
+pub fn points(place: u64, tied: u64) -> u64 {
+    let first = place.saturating_sub(1) as usize;
+    let total: u64 = POINTS.iter().skip(first).take(tied as usize).sum();
+    total / tied.max(1)
+}
+
+/// The place of an athlete in the final ranking of an event
+#[derive(Clone, Debug, PartialEq)]
+pub struct Placing {
+    pub athlete: Athlete,
+    pub rank: u64,
+}
+
+/// The final ranking of a category at an event
+#[derive(Clone, Debug, PartialEq)]
+pub struct EventResult {
+    pub event_id: u64,
+    pub event: String,
+    pub placings: Vec<Placing>,
+}
+
+/// The results of all events of a season for a category, like `BOULDER Men`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Category {
+    pub name: String,
+    pub events: Vec<EventResult>,
+}
+
+/// The points an athlete got at an event
+#[derive(Clone, Debug, PartialEq)]
+pub struct EventPoints {
+    pub rank: u64,
+    pub points: u64,
+    /// Whether the points count for the total, see the best-N rule in
+    /// [`standings`]
+    pub counted: bool,
+}
+
+/// An athlete in the overall ranking
+#[derive(Clone, Debug, PartialEq)]
+pub struct Standing {
+    pub athlete: Athlete,
+    pub rank: usize,
+    pub points: u64,
+    /// The points per event, in the same order as the events of the category
+    pub events: Vec<Option<EventPoints>>,
+}
+
+/// An athlete in the results of all rounds of a category at an event
+#[derive(Clone, Debug, PartialEq)]
+pub struct FullResult {
+    pub placing: Placing,
+    /// The rank in every round, in the order of the rounds, or `None` if the
+    /// athlete didn't climb in the round
+    pub rounds: Vec<Option<u64>>,
+}
+
+/// Combine the rounds of a category at an event, in the order they were
+/// climbed, into the final ranking.
+///
+/// Athletes that reached a later round are ranked before the others. Athletes
+/// that were eliminated in the same round are ranked by their rank in that
+/// round, and ties are broken by their ranks in the previous rounds.
+pub fn event_ranking(rounds: &[Results]) -> Vec<Placing> {
+    full_results(rounds)
+        .into_iter()
+        .map(|r| r.placing)
+        .collect()
+}
+
+/// The final ranking like [`event_ranking`], with the rank of every athlete in
+/// every round.
+pub fn full_results(rounds: &[Results]) -> Vec<FullResult> {
+    let mut athletes: Vec<(Athlete, Vec<Option<u64>>)> = Vec::new();
+    for (i, round) in rounds.iter().enumerate() {
+        for rank_athlete in &round.ranking {
+            let id = rank_athlete.athlete.athlete_id;
+            let index = match athletes.iter().position(|(a, _)| a.athlete_id == id) {
+                Some(index) => index,
+                None => {
+                    athletes.push((rank_athlete.athlete.clone(), vec![None; rounds.len()]));
+                    athletes.len() - 1
+                }
+            };
+            athletes[index].1[i] = rank_athlete.rank;
+        }
+    }
+
+    // Athletes without a rank in any round did not start
+    athletes.retain(|(_, ranks)| ranks.iter().any(Option::is_some));
+
+    // The ranks from the last round that was reached back to the first one,
+    // an athlete is better if this is smaller.
+    let key = |ranks: &[Option<u64>]| {
+        let reached = ranks.iter().rposition(Option::is_some).unwrap_or(0);
+        let ranks: Vec<_> = ranks[..=reached]
+            .iter()
+            .rev()
+            .map(|r| r.unwrap_or(u64::MAX))
+            .collect();
+        (std::cmp::Reverse(reached), ranks)
+    };
+    let keys: Vec<_> = athletes.iter().map(|(_, ranks)| key(ranks)).collect();
+
+    let mut results: Vec<_> = athletes
+        .into_iter()
+        .zip(&keys)
+        .map(|((athlete, rounds), k)| FullResult {
+            placing: Placing {
+                athlete,
+                rank: 1 + keys.iter().filter(|other| *other < k).count() as u64,
+            },
+            rounds,
+        })
+        .collect();
+    results.sort_by_key(|r| r.placing.rank);
+    results
+}
+
+/// The overall ranking of a category. If `best` is given, only the best
+/// `best` results of every athlete count for their total.
+pub fn standings(events: &[EventResult], best: Option<usize>) -> Vec<Standing> {
+    let mut standings: Vec<Standing> = Vec::new();
+    for (i, event) in events.iter().enumerate() {
+        for placing in &event.placings {
+            let tied = event
+                .placings
+                .iter()
+                .filter(|p| p.rank == placing.rank)
+                .count() as u64;
+            let id = placing.athlete.athlete_id;
+            let index = match standings.iter().position(|s| s.athlete.athlete_id == id) {
+                Some(index) => index,
+                None => {
+                    standings.push(Standing {
+                        athlete: placing.athlete.clone(),
+                        rank: 0,
+                        points: 0,
+                        events: vec![None; events.len()],
+                    });
+                    standings.len() - 1
+                }
+            };
+            standings[index].events[i] = Some(EventPoints {
+                rank: placing.rank,
+                points: points(placing.rank, tied),
+                counted: true,
+            });
+        }
+    }
+
+    for standing in &mut standings {
+        let mut results: Vec<_> = standing.events.iter_mut().flatten().collect();
+        results.sort_by(|a, b| b.points.cmp(&a.points));
+        for (i, result) in results.into_iter().enumerate() {
+            result.counted = best.map_or(true, |best| i < best);
+        }
+        standing.points = standing
+            .events
+            .iter()
+            .flatten()
+            .filter(|r| r.counted)
+            .map(|r| r.points)
+            .sum();
+    }
+
+    let totals: Vec<_> = standings.iter().map(|s| s.points).collect();
+    for standing in &mut standings {
+        standing.rank = 1 + totals.iter().filter(|&&t| t > standing.points).count();
+    }
+    standings.sort_by(|a, b| {
+        a.rank
+            .cmp(&b.rank)
+            .then_with(|| a.athlete.lastname.cmp(&b.athlete.lastname))
+    });
+    standings
+}
+
+/// Fetch the final results of every finished World Cup of a season, grouped by
+/// category.
+pub async fn fetch_season(season_id: u64) -> Option<Vec<Category>> {
+    let season: Season = api::request(format!("seasons/{season_id}")).await?;
+    let now = chrono::offset::Utc::now();
+    let mut events = season.events;
+    events.retain(|e| e.ends_at < now && is_world_cup(&e.league_name, &e.event));
+    events.sort_by_key(|e| e.starts_at);
+
+    let mut categories: Vec<Category> = Vec::new();
+    for short_event in &events {
+        let Some(event_categories) = fetch_event(short_event).await else {
+            continue;
+        };
+        for event_category in event_categories {
+            match categories
+                .iter_mut()
+                .find(|c| c.name == event_category.name)
+            {
+                Some(category) => category.events.extend(event_category.events),
+                None => categories.push(event_category),
+            }
+        }
+    }
+    Some(categories)
+}
+
+/// Fetch the final results of every category of an event, so every category
+/// has a single event. The rounds that could not be loaded are left out.
+pub async fn fetch_event(short_event: &ShortEvent) -> Option<Vec<Category>> {
+    let event: Event = api::request(format!("events/{}", short_event.event_id)).await?;
+
+    let mut categories = Vec::new();
+    for dcat in event.dcats {
+        let rounds: Vec<_> = fetch_rounds(&dcat)
+            .await
+            .into_iter()
+            .filter_map(|(_, results)| results)
+            .collect();
+        let placings = event_ranking(&rounds);
+        if placings.is_empty() {
+            continue;
+        }
+
+        categories.push(Category {
+            name: dcat.dcat_name,
+            events: vec![EventResult {
+                event_id: short_event.event_id,
+                event: short_event.event.clone(),
+                placings,
+            }],
+        });
+    }
+    Some(categories)
+}
+
+/// The rounds of a category with their results, or `None` for the rounds
+/// whose results could not be loaded
+pub async fn fetch_rounds(category: &ShortCategory) -> Vec<(CategoryRound, Option<Results>)> {
+    let mut rounds = Vec::new();
+    for round in &category.category_rounds {
+        let url = format!("category_rounds/{}/results", round.category_round_id);
+        rounds.push((round.clone(), api::request::<Results>(url).await));
+    }
+    rounds
+}
@@ -0,0 +1,362 @@
+//! The pages with the overall ranking of a season, the results of all rounds
+//! of a category at an event and the standings of the countries
+use super::{
+    fetch_event, fetch_rounds, fetch_season, full_results, medal_table, standings, team_ranking,
+    TEAM_ATHLETES,
+};
+use crate::api::seasons::{RoundStatus, ShortCategory, ShortEvent, ShortSeason};
+use crate::predictions::PredictionForm;
+use crate::{leaderboard::LeaderboardInput, rating::SharedRatings, Page};
+use dioxus::prelude::*;
+
+pub fn Standings(cx: Scope<ShortSeason>) -> Element {
+    let id = cx.props.id;
+    let future = use_future(cx, (&id,), |_| fetch_season(id));
+    let selected = use_state(cx, || 0);
+    let best = use_state(cx, || None::<usize>);
+
+    let categories = match future.value() {
+        Some(Some(categories)) if !categories.is_empty() => categories,
+        Some(Some(_)) => return cx.render(rsx! { "No finished events yet" }),
+        Some(None) => return cx.render(rsx! { "Could not load the season" }),
+        None => return cx.render(rsx! { "Loading..." }),
+    };
+
+    let index = (*selected.get()).min(categories.len() - 1);
+    let category = &categories[index];
+    let standings = standings(&category.events, *best.get());
+
+    let tabs = categories.iter().enumerate().map(|(i, c)| {
+        rsx! {
+            button {
+                class: if i == index { "selected" } else { "" },
+                onclick: move |_| selected.set(i),
+                "{c.name}"
+            }
+        }
+    });
+
+    let rows = standings.iter().map(|s| {
+        let events = s.events.iter().map(|e| match e {
+            Some(e) => rsx! {
+                td {
+                    class: if e.counted { "" } else { "dropped" },
+                    title: "Place {e.rank}",
+                    "{e.points}"
+                }
+            },
+            None => rsx! { td {} },
+        });
+        rsx! {
+            tr {
+                key: "{s.athlete.athlete_id}",
+                td { "{s.rank}" }
+                td { "{s.athlete.firstname} {s.athlete.lastname}" }
+                td { "{s.athlete.country.country}" }
+                td { class: "total", "{s.points}" }
+                events
+            }
+        }
+    });
+
+    cx.render(rsx! {
+        h1 { "{cx.props.name} World Cup ranking" }
+        div {
+            class: "actions",
+            tabs
+        }
+        div {
+            class: "form",
+            "Count the best "
+            input {
+                r#type: "number",
+                min: "1",
+                placeholder: "all",
+                oninput: move |e| best.set(e.value.parse().ok()),
+            }
+            " results"
+        }
+        table {
+            class: "standings",
+            thead {
+                tr {
+                    th { "Rank" }
+                    th { "Athlete" }
+                    th { "Country" }
+                    th { "Points" }
+                    category.events.iter().map(|e| rsx! { th { "{e.event}" } })
+                }
+            }
+            tbody { rows }
+        }
+    })
+}
+
+/// What the standings of the countries are computed for
+#[derive(Clone, PartialEq)]
+pub enum CountryScope {
+    Event(ShortEvent),
+    Season(ShortSeason),
+}
+
+#[derive(PartialEq, Props)]
+pub struct CountriesProps {
+    scope: CountryScope,
+}
+
+pub fn Countries(cx: Scope<CountriesProps>) -> Element {
+    let scope = cx.props.scope.clone();
+    let future = use_future(cx, (&cx.props.scope,), |_| async move {
+        match scope {
+            CountryScope::Event(event) => fetch_event(&event).await,
+            CountryScope::Season(season) => fetch_season(season.id).await,
+        }
+    });
+
+    let title = match &cx.props.scope {
+        CountryScope::Event(event) => event.event.clone(),
+        CountryScope::Season(season) => format!("{} season", season.name),
+    };
+
+    let categories = match future.value() {
+        Some(Some(categories)) if !categories.is_empty() => categories,
+        Some(Some(_)) => return cx.render(rsx! { "No results yet" }),
+        Some(None) => return cx.render(rsx! { "Could not load the results" }),
+        None => return cx.render(rsx! { "Loading..." }),
+    };
+    let results = || categories.iter().flat_map(|c| &c.events);
+
+    let medals = medal_table(results()).into_iter().map(|m| {
+        let total = m.total();
+        rsx! {
+            tr {
+                key: "{m.country}",
+                td { "{m.rank}" }
+                td { "{m.country}" }
+                td { "{m.gold}" }
+                td { "{m.silver}" }
+                td { "{m.bronze}" }
+                td { class: "total", "{total}" }
+            }
+        }
+    });
+
+    let teams = team_ranking(results()).into_iter().map(|t| {
+        rsx! {
+            tr {
+                key: "{t.country}",
+                td { "{t.rank}" }
+                td { "{t.country}" }
+                td { class: "total", "{t.points}" }
+            }
+        }
+    });
+
+    cx.render(rsx! {
+        h1 { "{title}" }
+        h2 { "Medals" }
+        table {
+            class: "standings",
+            thead {
+                tr {
+                    th { "Rank" }
+                    th { "Country" }
+                    th { "Gold" }
+                    th { "Silver" }
+                    th { "Bronze" }
+                    th { "Total" }
+                }
+            }
+            tbody { medals }
+        }
+        h2 { "National teams" }
+        div { "The points of the best {TEAM_ATHLETES} athletes of every country per category count" }
+        table {
+            class: "standings",
+            thead {
+                tr {
+                    th { "Rank" }
+                    th { "Country" }
+                    th { "Points" }
+                }
+            }
+            tbody { teams }
+        }
+    })
+}
+
+#[derive(PartialEq, Props)]
+pub struct EventCategoryProps {
+    event: ShortEvent,
+    category: ShortCategory,
+}
+
+/// The rank of every athlete in every round of a category at an event, with
+/// the final ranking of the event
+pub fn EventCategory(cx: Scope<EventCategoryProps>) -> Element {
+    let category = cx.props.category.clone();
+    // The rounds that have started but could not be loaded are shown by name,
+    // the upcoming rounds often don't have results yet
+    let event_ends_at = cx.props.event.ends_at;
+    let future = use_future(cx, (&cx.props.category,), |_| async move {
+        let now = chrono::offset::Utc::now();
+        let mut rounds = Vec::new();
+        let mut failed = Vec::new();
+        for (round, results) in fetch_rounds(&category).await {
+            match results {
+                Some(results) => rounds.push((round, results)),
+                None if round.round_status(event_ends_at, now) != RoundStatus::Upcoming => {
+                    failed.push(round.name)
+                }
+                None => {}
+            }
+        }
+        (rounds, failed)
+    });
+    let page = use_shared_state::<Page>(cx).unwrap();
+    let ratings = use_shared_state::<SharedRatings>(cx).unwrap();
+
+    let (rounds, failed) = match future.value() {
+        Some((rounds, failed)) if !rounds.is_empty() || !failed.is_empty() => (rounds, failed),
+        Some(_) => return cx.render(rsx! { "No results yet" }),
+        None => return cx.render(rsx! { "Loading..." }),
+    };
+    let failed = failed.join(", ");
+    let results: Vec<_> = rounds.iter().map(|(_, results)| results.clone()).collect();
+
+    let headers = rounds.iter().map(|(round, _)| {
+        let event_name = cx.props.event.event.clone();
+        let round_id = round.category_round_id;
+        rsx! {
+            th {
+                class: "round-link",
+                onclick: move |_| *page.write() = Page::Leaderboard(LeaderboardInput::Api(event_name.clone(), round_id)),
+                "{round.name}"
+            }
+        }
+    });
+
+    let rows = full_results(&results).into_iter().map(|r| {
+        let athlete = &r.placing.athlete;
+        let athlete_id = athlete.athlete_id;
+        let ranks = r.rounds.iter().map(|rank| match rank {
+            Some(rank) => rsx! { td { "{rank}" } },
+            None => rsx! { td {} },
+        });
+        rsx! {
+            tr {
+                key: "{athlete.athlete_id}",
+                td { class: "total", "{r.placing.rank}" }
+                td {
+                    class: "athlete-link",
+                    onclick: move |_| *page.write() = Page::Athlete(athlete_id),
+                    "{athlete.firstname} {athlete.lastname}"
+                }
+                td { "{athlete.country.country}" }
+                ranks
+            }
+        }
+    });
+
+    // The first round with a start list but without ranks is predicted from
+    // the ratings, if there are any
+    let ratings = ratings.read();
+    let ratings = match &*ratings {
+        SharedRatings::Loaded(ratings) => ratings.as_ref(),
+        SharedRatings::Loading => None,
+    };
+    let prediction = ratings.and_then(|ratings| {
+        let (round, results) = rounds.iter().find(|(_, r)| {
+            !r.ranking.is_empty() && r.ranking.iter().all(|a| a.rank.is_none())
+        })?;
+        let athletes: Vec<_> = results.ranking.iter().map(|a| a.athlete.clone()).collect();
+        let rows = ratings
+            .predict(&results.discipline, &athletes)
+            .into_iter()
+            .map(|p| {
+                let rating = p.rating.round();
+                rsx! {
+                    tr {
+                        key: "{p.athlete.athlete_id}",
+                        td { class: "total", "{p.rank}" }
+                        td { "{p.athlete.firstname} {p.athlete.lastname}" }
+                        td { "{p.athlete.country.country}" }
+                        td { "{rating}" }
+                    }
+                }
+            });
+        Some(rsx! {
+            h2 { "Predicted {round.name}" }
+            table {
+                class: "standings",
+                thead {
+                    tr {
+                        th { "Rank" }
+                        th { "Athlete" }
+                        th { "Country" }
+                        th { "Rating" }
+                    }
+                }
+                tbody { rows }
+            }
+        })
+    });
+
+    // The next round can be predicted, with the athletes of its start list or
+    // otherwise the athletes of the rounds before it
+    let now = chrono::offset::Utc::now();
+    let form = cx
+        .props
+        .category
+        .category_rounds
+        .iter()
+        .find(|r| r.round_status(event_ends_at, now) == RoundStatus::Upcoming)
+        .and_then(|round| {
+            let start_list: Vec<_> = rounds
+                .iter()
+                .find(|(r, _)| r.category_round_id == round.category_round_id)
+                .map(|(_, results)| results.ranking.iter().map(|a| a.athlete.clone()).collect())
+                .unwrap_or_default();
+            let athletes: Vec<_> = if start_list.is_empty() {
+                full_results(&results)
+                    .into_iter()
+                    .map(|r| r.placing.athlete)
+                    .collect()
+            } else {
+                start_list
+            };
+            if athletes.is_empty() {
+                return None;
+            }
+            Some(rsx! {
+                PredictionForm {
+                    event: cx.props.event.clone(),
+                    category: cx.props.category.dcat_name.clone(),
+                    round: round.clone(),
+                    athletes: athletes,
+                }
+            })
+        });
+
+    cx.render(rsx! {
+        h1 { "{cx.props.event.event}" }
+        h2 { "{cx.props.category.dcat_name}" }
+        if !failed.is_empty() {
+            rsx! { div { class: "hint", "Could not load the results of {failed}" } }
+        }
+        table {
+            class: "standings",
+            thead {
+                tr {
+                    th { "Rank" }
+                    th { "Athlete" }
+                    th { "Country" }
+                    headers
+                }
+            }
+            tbody { rows }
+        }
+        prediction
+        form
+    })
+}
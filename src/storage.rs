@@ -0,0 +1,60 @@
+//! Persistent local data, stored in localStorage on the web and in json files
+//! on desktop.
+use serde::{de::DeserializeOwned, Serialize};
+use std::fmt::Display;
+
+/// Data that could not be saved, like in a read-only directory or a full
+/// localStorage. This is also the shared state with the last error, which is
+/// shown at the top of every page.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SaveError(String);
+
+impl Display for SaveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Could not save: {}", self.0)
+    }
+}
+
+#[cfg(feature = "desktop")]
+fn path(key: &str) -> std::path::PathBuf {
+    let mut path = std::path::PathBuf::from("data");
+    path.push(key);
+    path.set_extension("json");
+    path
+}
+
+#[cfg(feature = "desktop")]
+pub fn load<T: DeserializeOwned>(key: &str) -> Option<T> {
+    let path = path(key);
+    let s = std::fs::read_to_string(&path).ok()?;
+    match serde_json::from_str(&s) {
+        Ok(value) => Some(value),
+        Err(e) => {
+            eprintln!("Could not read {}: {e}", path.display());
+            None
+        }
+    }
+}
+
+#[cfg(feature = "desktop")]
+pub fn save<T: Serialize>(key: &str, value: &T) -> Result<(), SaveError> {
+    let path = path(key);
+    let error = |e: &dyn Display| SaveError(format!("{}: {e}", path.display()));
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| error(&e))?;
+    }
+    let json = serde_json::to_string_pretty(value).map_err(|e| error(&e))?;
+    std::fs::write(&path, json).map_err(|e| error(&e))
+}
+
+#[cfg(feature = "web")]
+pub fn load<T: DeserializeOwned>(key: &str) -> Option<T> {
+    use gloo_storage::Storage;
+    gloo_storage::LocalStorage::get(key).ok()
+}
+
+#[cfg(feature = "web")]
+pub fn save<T: Serialize>(key: &str, value: &T) -> Result<(), SaveError> {
+    use gloo_storage::Storage;
+    gloo_storage::LocalStorage::set(key, value).map_err(|e| SaveError(format!("{key}: {e}")))
+}
@@ -0,0 +1,104 @@
+use crate::api::{
+    self, mock,
+    result::{DisciplineTag, Results},
+    seasons::{Event, RoundStatus, Season, SeasonsResponse},
+};
+use crate::discipline::Boulder;
+use crate::ranking;
+
+#[tokio::test]
+async fn seasons() {
+    mock::start();
+    let SeasonsResponse { seasons } = api::request::<SeasonsResponse>(String::new())
+        .await
+        .unwrap();
+    assert_eq!(seasons.len(), 2);
+    assert_eq!(seasons[0].id, 35);
+    assert_eq!(seasons[0].name, "2023");
+}
+
+#[tokio::test]
+async fn season_events() {
+    mock::start();
+    let season = api::request::<Season>("seasons/35".into()).await.unwrap();
+    assert_eq!(season.name, "2023");
+    let ids: Vec<_> = season.events.iter().map(|e| e.event_id).collect();
+    assert_eq!(ids, [1291, 1297, 1329, 1337]);
+}
+
+#[tokio::test]
+async fn event_with_php_warnings() {
+    mock::start();
+    let event = api::request::<Event>("events/1297".into()).await.unwrap();
+    let names: Vec<_> = event.dcats.iter().map(|c| c.dcat_name.as_str()).collect();
+    assert_eq!(names, ["BOULDER Men", "BOULDER Women"]);
+    let rounds: Vec<_> = event.dcats[1]
+        .category_rounds
+        .iter()
+        .map(|r| r.category_round_id)
+        .collect();
+    assert_eq!(rounds, [7686, 8251, 8252]);
+}
+
+#[tokio::test]
+async fn round_status() {
+    mock::start();
+    let mut event = api::request::<Event>("events/1297".into()).await.unwrap();
+    let now = chrono::Utc::now();
+    assert!(event.live_round(now).is_none());
+
+    // Without a status we know, the schedule decides
+    let ends_at = now + chrono::Duration::days(1);
+    let round = &mut event.dcats[1].category_rounds[2];
+    round.status = String::new();
+    assert_eq!(round.round_status(ends_at, now), RoundStatus::Upcoming);
+    round.starts_at = Some(now - chrono::Duration::hours(1));
+    assert_eq!(round.round_status(ends_at, now), RoundStatus::Live);
+    round.ends_at = Some(now - chrono::Duration::minutes(5));
+    assert_eq!(round.round_status(ends_at, now), RoundStatus::Finished);
+
+    // Without a schedule either, the end of the event decides
+    round.starts_at = None;
+    round.ends_at = None;
+    assert_eq!(
+        round.round_status(event.ends_at, now),
+        RoundStatus::Finished
+    );
+
+    event.dcats[1].category_rounds[2].status = "active".to_string();
+    let (category, round) = event.live_round(now).unwrap();
+    assert_eq!(category.dcat_name, "BOULDER Women");
+    assert_eq!(round.category_round_id, 8252);
+}
+
+#[tokio::test]
+async fn missing_endpoint() {
+    mock::start();
+    assert!(api::request::<Season>("seasons/1".into()).await.is_none());
+}
+
+#[tokio::test]
+async fn boulder_ranking() {
+    mock::start();
+    let results = api::request::<Results>("category_rounds/8251/results/".into())
+        .await
+        .unwrap();
+    assert_eq!(results.discipline, DisciplineTag::Boulder);
+    assert_eq!(results.round, "Semi-final");
+
+    let mut athletes = ranking::rank::<Boulder>(&results);
+    athletes.sort_by_key(|a| a.rank);
+    let ids: Vec<_> = athletes.iter().take(3).map(|a| a.id).collect();
+    assert_eq!(ids, [1811, 2357, 2294]);
+}
+
+#[tokio::test]
+async fn lead_results() {
+    mock::start();
+    let results = api::request::<Results>("category_rounds/7977/results/".into())
+        .await
+        .unwrap();
+    assert_eq!(results.discipline, DisciplineTag::Lead);
+    assert_eq!(results.category, "Men B1");
+    assert_eq!(results.ranking.len(), 4);
+}
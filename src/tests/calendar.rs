@@ -0,0 +1,79 @@
+//! Dates and times of events in the timezone of the venue
+use crate::api::seasons::Season;
+use crate::calendar::{self, Span};
+use chrono::NaiveDate;
+
+fn season() -> Season {
+    let path = format!(
+        "{}/test_data/api/season_35.json",
+        env!("CARGO_MANIFEST_DIR")
+    );
+    serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+}
+
+fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(y, m, d).unwrap()
+}
+
+#[test]
+fn month_and_week() {
+    // June 2023 starts on a Thursday and ends on a Friday
+    let days = calendar::days(Span::Month, date(2023, 6, 14));
+    assert_eq!(days.len(), 35);
+    assert_eq!(days[0], date(2023, 5, 29));
+    assert_eq!(days[34], date(2023, 7, 2));
+
+    let days = calendar::days(Span::Week, date(2023, 6, 14));
+    assert_eq!(
+        days,
+        date(2023, 6, 12).iter_days().take(7).collect::<Vec<_>>()
+    );
+
+    assert_eq!(
+        calendar::step(Span::Month, date(2023, 1, 31), true),
+        date(2023, 2, 1)
+    );
+    assert_eq!(
+        calendar::step(Span::Month, date(2023, 3, 31), false),
+        date(2023, 2, 1)
+    );
+    assert_eq!(
+        calendar::step(Span::Week, date(2023, 6, 14), true),
+        date(2023, 6, 21)
+    );
+}
+
+#[test]
+fn venue_dates_and_times() {
+    let season = season();
+    let innsbruck = season
+        .events
+        .iter()
+        .find(|e| e.location == "Innsbruck")
+        .unwrap();
+
+    // The event starts at midnight in Innsbruck, which is still the day
+    // before in UTC
+    assert_eq!(
+        calendar::local_dates(innsbruck),
+        (date(2023, 6, 14), date(2023, 6, 18))
+    );
+    assert_eq!(innsbruck.tz(), Some(chrono_tz::Europe::Vienna));
+    assert_eq!(
+        calendar::venue_time(innsbruck.starts_at, innsbruck.tz()),
+        "Jun 14 00:00 CEST"
+    );
+    assert_eq!(
+        calendar::venue_time(innsbruck.starts_at, None),
+        "Jun 13 22:00 UTC"
+    );
+
+    let on_day = |day| -> Vec<_> {
+        calendar::events_on(&season.events, day)
+            .iter()
+            .map(|e| e.location.clone())
+            .collect()
+    };
+    assert_eq!(on_day(date(2023, 6, 14)), ["Innsbruck"]);
+    assert!(on_day(date(2023, 6, 13)).is_empty());
+}
@@ -0,0 +1,107 @@
+//! Compare our rankings with the official rankings of every fixture in
+//! `test_data`.
+use crate::api::result::{DisciplineTag, Results};
+use crate::discipline::{Boulder, Discipline, Lead, Score, Speed};
+use crate::ranking;
+use std::path::PathBuf;
+
+/// Fixtures that are known to disagree with the official ranking, because
+/// the ascents were captured in the middle of an update of the scores.
+const INCONSISTENT: &[&str] = &["results_boulder_finished.json"];
+
+/// Official scores of athletes that did not start, did not finish or were
+/// disqualified, which are not scores in the official notation
+const NOT_SCORED: &[&str] = &["DNS", "DNF", "DSQ"];
+
+/// Every fixture in `test_data` with the results of a round, by file name
+fn fixtures() -> Vec<(String, Results)> {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("test_data");
+    let mut paths: Vec<_> = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().map_or(false, |e| e == "json"))
+        .collect();
+    paths.sort();
+
+    paths
+        .into_iter()
+        .map(|path| {
+            let name = path.file_name().unwrap().to_string_lossy().to_string();
+            let results = serde_json::from_str(&std::fs::read_to_string(&path).unwrap())
+                .unwrap_or_else(|e| panic!("could not parse {name}: {e}"));
+            (name, results)
+        })
+        .collect()
+}
+
+#[test]
+fn golden_rankings() {
+    let mut failures = Vec::new();
+    for (name, results) in fixtures() {
+        let mismatches = ranking::disagreements(&results);
+
+        println!("{name}: {} mismatches", mismatches.len());
+        for m in &mismatches {
+            println!("  {m}");
+        }
+
+        if !mismatches.is_empty() && !INCONSISTENT.contains(&name.as_str()) {
+            failures.push(name);
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "rankings differ from the official rankings in {failures:?}"
+    );
+}
+
+/// Parse every official score and check that formatting it gives the same
+/// notation, and that our own scores are written the same way.
+fn check_notation<D: Discipline>(name: &str, results: &Results) -> Vec<String> {
+    let ours = ranking::extract_athletes::<D>(results);
+    let mut failures = Vec::new();
+    for (official, ours) in results.ranking.iter().zip(ours) {
+        let Some(notation) = official
+            .score
+            .as_deref()
+            .filter(|s| !s.is_empty() && !NOT_SCORED.contains(s))
+        else {
+            continue;
+        };
+        match D::Score::parse_official(notation) {
+            Some(score) if score.official() == notation => {}
+            Some(score) => failures.push(format!(
+                "{name}: {notation:?} is formatted as {:?}",
+                score.official()
+            )),
+            None => failures.push(format!("{name}: could not parse {notation:?}")),
+        }
+        if D::Score::COMPARABLE
+            && !INCONSISTENT.contains(&name)
+            && ours.score.official() != notation
+        {
+            failures.push(format!(
+                "{name}: we score {} {} as {:?}, officially {notation:?}",
+                ours.first_name,
+                ours.last_name,
+                ours.score.official()
+            ));
+        }
+    }
+    failures
+}
+
+#[test]
+fn official_notation() {
+    let failures: Vec<_> = fixtures()
+        .iter()
+        .flat_map(|(name, results)| match results.discipline {
+            DisciplineTag::Lead => check_notation::<Lead>(name, results),
+            DisciplineTag::Boulder => check_notation::<Boulder>(name, results),
+            DisciplineTag::Speed => check_notation::<Speed>(name, results),
+            DisciplineTag::Unknown => Vec::new(),
+        })
+        .collect();
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
@@ -0,0 +1,66 @@
+//! Comparing two athletes over the rounds in which both of them climbed
+use crate::api::{athlete::AthleteResult, mock, result::DisciplineTag};
+use crate::head_to_head::{self, Record, Winner};
+
+const GROSSMAN: u64 = 1803;
+const RABOUTOU: u64 = 1811;
+
+#[tokio::test]
+async fn rivals_in_innsbruck() {
+    mock::start();
+    let comparison = head_to_head::compare(GROSSMAN, RABOUTOU).await.unwrap();
+    assert_eq!(comparison.first.lastname, "GROSSMAN");
+
+    // Only the qualification and the semi-final of the women are served by
+    // the mock
+    let meetings: Vec<_> = comparison
+        .meetings
+        .iter()
+        .map(|m| (m.round_id, m.ranks, m.winner()))
+        .collect();
+    assert_eq!(
+        meetings,
+        [
+            (7686, (1, 3), Winner::First),
+            (8251, (5, 1), Winner::Second),
+        ]
+    );
+
+    assert_eq!(
+        head_to_head::records(&comparison.meetings),
+        [Record {
+            discipline: DisciplineTag::Boulder,
+            first: 1,
+            second: 1,
+            ties: 0,
+        }]
+    );
+}
+
+#[tokio::test]
+async fn unknown_athlete() {
+    mock::start();
+    assert!(head_to_head::compare(GROSSMAN, 1).await.is_none());
+
+    // An athlete compared with themselves only ties
+    let comparison = head_to_head::compare(RABOUTOU, RABOUTOU).await.unwrap();
+    assert_eq!(comparison.meetings.len(), 2);
+    assert!(comparison
+        .meetings
+        .iter()
+        .all(|m| m.winner() == Winner::Tie));
+}
+
+#[test]
+fn shared_categories_only() {
+    let result = |discipline: &str, category_name: &str| AthleteResult {
+        event_id: 1,
+        discipline: discipline.to_string(),
+        category_name: category_name.to_string(),
+    };
+    assert!(result("boulder", "Women").is_in("BOULDER Women"));
+    assert!(!result("boulder", "Women").is_in("BOULDER Men"));
+    assert!(!result("lead", "Women").is_in("BOULDER Women"));
+    // Results without a category could be in any category
+    assert!(result("", "").is_in("LEAD Men"));
+}
@@ -0,0 +1,142 @@
+//! The local database with the results of past seasons
+use crate::api::{mock, result::DisciplineTag};
+use crate::history::{self, History, HistoryEntry, Statistics, Synced};
+use crate::standings;
+
+const GROSSMAN: u64 = 1803;
+
+#[tokio::test]
+async fn sync_and_query() {
+    mock::start();
+    let history = History::open_in_memory().unwrap();
+
+    // Only the World Cup and the paraclimbing event in Innsbruck are served by
+    // the mock, the World Cup without the finals
+    let synced = history.sync_season(35).await.unwrap().unwrap();
+    assert_eq!(
+        synced,
+        Synced {
+            events: 2,
+            rounds: 4,
+            failed_events: 2,
+        }
+    );
+    assert!(!history.is_complete(1297).unwrap());
+    assert!(history.is_complete(1337).unwrap());
+
+    // The World Cup is incomplete, so it is synced again, replacing the rounds
+    let synced = history.sync_season(35).await.unwrap().unwrap();
+    assert_eq!(synced.rounds, 3);
+
+    let entries = history.athlete_history(GROSSMAN).unwrap();
+    let rounds: Vec<_> = entries
+        .iter()
+        .map(|e| (e.round_id, e.round.as_str(), e.rank))
+        .collect();
+    assert_eq!(
+        rounds,
+        [
+            (7686, "Qualification", Some(1)),
+            (8251, "Semi-final", Some(5))
+        ]
+    );
+    assert_eq!(entries[0].season, "2023");
+    assert_eq!(entries[0].category, "BOULDER Women");
+    assert_eq!(entries[0].discipline, DisciplineTag::Boulder);
+
+    // The stored rounds give the same ranking as the rounds from the API
+    let stored = history.rounds(1297, "BOULDER Women").unwrap();
+    assert_eq!(stored.len(), 2);
+    let mut fetched = Vec::new();
+    for round in [7686, 8251] {
+        fetched.push(
+            crate::api::request(format!("category_rounds/{round}/results"))
+                .await
+                .unwrap(),
+        );
+    }
+    assert_eq!(
+        standings::event_ranking(&stored),
+        standings::event_ranking(&fetched)
+    );
+
+    // The paraclimbing event is stored, but it doesn't count for the World Cup
+    let categories = history.season_categories(35).unwrap();
+    let names: Vec<_> = categories.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, ["BOULDER Men", "BOULDER Women"]);
+    assert_eq!(categories[1].events[0].event_id, 1297);
+}
+
+#[tokio::test]
+async fn unknown_season() {
+    mock::start();
+    let history = History::open_in_memory().unwrap();
+    assert_eq!(history.sync_season(1).await.unwrap(), None);
+    assert!(history.athlete_history(GROSSMAN).unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn stored_rows() {
+    mock::start();
+    let path = std::env::temp_dir().join(format!("history-{}.sqlite", std::process::id()));
+    let history = History::open(&path).unwrap();
+    history.sync_season(35).await.unwrap().unwrap();
+
+    let connection = rusqlite::Connection::open(&path).unwrap();
+    let discipline: String = connection
+        .query_row("SELECT discipline FROM rounds WHERE id = 7686", [], |row| {
+            row.get(0)
+        })
+        .unwrap();
+    assert_eq!(discipline, "Boulder");
+
+    // A row with a time we can't read is an error, not the year 1970
+    connection
+        .execute("UPDATE events SET starts_at = 'soon'", [])
+        .unwrap();
+    assert!(history.athlete_history(GROSSMAN).is_err());
+    assert!(history.ratings().is_err());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn statistics_per_discipline() {
+    let entry = |discipline: DisciplineTag, round: &str, rank: Option<u64>| HistoryEntry {
+        season: "2023".into(),
+        event: "Event".into(),
+        starts_at: Default::default(),
+        category: "Women".into(),
+        discipline,
+        round: round.into(),
+        round_id: 0,
+        rank,
+    };
+    let entries = [
+        entry(DisciplineTag::Boulder, "Semi-final", Some(2)),
+        entry(DisciplineTag::Boulder, "Final", Some(1)),
+        entry(DisciplineTag::Lead, "Qualification", None),
+        entry(DisciplineTag::Boulder, "Final", Some(4)),
+        entry(DisciplineTag::Lead, "Final", Some(3)),
+    ];
+    assert_eq!(
+        history::statistics(&entries),
+        [
+            Statistics {
+                discipline: DisciplineTag::Boulder,
+                rounds: 3,
+                finals: 2,
+                wins: 1,
+                podiums: 1,
+                best_rank: Some(1),
+            },
+            Statistics {
+                discipline: DisciplineTag::Lead,
+                rounds: 2,
+                finals: 1,
+                wins: 0,
+                podiums: 1,
+                best_rank: Some(3),
+            },
+        ]
+    );
+}
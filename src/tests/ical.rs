@@ -0,0 +1,159 @@
+//! Calendars of the events and the schedule of their rounds
+use crate::api::{mock, seasons::Event};
+use crate::filter::Filter;
+use crate::ical::{self, Entry};
+use chrono::{TimeZone, Utc};
+
+/// Event 1297 with a schedule for the qualification of the men
+fn innsbruck() -> Entry {
+    let path = format!(
+        "{}/test_data/api/event_1297.json",
+        env!("CARGO_MANIFEST_DIR")
+    );
+    let json = std::fs::read_to_string(path).unwrap();
+    // The fixture starts with PHP warnings, like the real API sometimes does
+    let mut event: Event = serde_json::from_str(&json[json.find('{').unwrap()..]).unwrap();
+    let round = &mut event.dcats[0].category_rounds[0];
+    round.starts_at = Some(Utc.with_ymd_and_hms(2023, 6, 14, 7, 0, 0).unwrap());
+    round.ends_at = Some(Utc.with_ymd_and_hms(2023, 6, 14, 17, 30, 0).unwrap());
+    Entry {
+        event: event.short(),
+        details: Some(event),
+    }
+}
+
+#[test]
+fn event_with_schedule() {
+    let calendar = ical::calendar("Innsbruck", &[innsbruck()], &Filter::default());
+    assert!(calendar.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+    assert!(calendar.ends_with("END:VCALENDAR\r\n"));
+    // Lines end in CRLF and are folded after 75 bytes
+    let lines: Vec<_> = calendar.split_terminator("\r\n").collect();
+    assert!(lines.iter().all(|l| l.len() <= 75 && !l.contains('\n')));
+
+    // The whole event, in the dates of the venue
+    assert!(lines.contains(&"UID:event-1297@boulderboard"));
+    assert!(lines.contains(&"DTSTART;VALUE=DATE:20230614"));
+    assert!(lines.contains(&"DTEND;VALUE=DATE:20230619"));
+    // The round, in the time of the venue
+    assert!(lines.contains(&"UID:round-7685@boulderboard"));
+    assert!(lines.contains(&"DTSTART;TZID=Europe/Vienna:20230614T090000"));
+    assert!(lines.contains(&"DTEND;TZID=Europe/Vienna:20230614T193000"));
+    assert!(lines.contains(&"SUMMARY:BOULDER Men Qualification - IFSC World Cup Innsbruck 2023"));
+    // Rounds without a schedule are left out
+    assert_eq!(lines.iter().filter(|l| **l == "BEGIN:VEVENT").count(), 2);
+
+    // The timezone of the venue, with the changes to and from summer time
+    let timezone = &lines[lines.iter().position(|l| *l == "BEGIN:VTIMEZONE").unwrap()..];
+    assert_eq!(timezone[1], "TZID:Europe/Vienna");
+    assert!(timezone.windows(4).any(|w| w
+        == [
+            "BEGIN:DAYLIGHT",
+            "DTSTART:20230326T020000",
+            "TZOFFSETFROM:+0100",
+            "TZOFFSETTO:+0200"
+        ]));
+    assert!(timezone.windows(4).any(|w| w
+        == [
+            "BEGIN:STANDARD",
+            "DTSTART:20231029T030000",
+            "TZOFFSETFROM:+0200",
+            "TZOFFSETTO:+0100"
+        ]));
+
+    // Exporting again gives exactly the same calendar
+    assert_eq!(
+        calendar,
+        ical::calendar("Innsbruck", &[innsbruck()], &Filter::default())
+    );
+}
+
+#[test]
+fn filter_by_discipline_and_category() {
+    let women = Filter {
+        discipline: Some("boulder".to_string()),
+        league: None,
+        category: Some("women".to_string()),
+    };
+    let calendar = ical::calendar("Innsbruck", &[innsbruck()], &women);
+    assert!(calendar.contains("UID:event-1297@boulderboard"));
+    // The only round with a schedule is of the men
+    assert!(!calendar.contains("UID:round-7685@boulderboard"));
+    assert!(!calendar.contains("BEGIN:VTIMEZONE"));
+
+    let lead = Filter {
+        discipline: Some("lead".to_string()),
+        ..Filter::default()
+    };
+    let calendar = ical::calendar("Innsbruck", &[innsbruck()], &lead);
+    assert!(!calendar.contains("BEGIN:VEVENT"));
+}
+
+#[test]
+fn typed_category() {
+    // The category is stored as it is typed, with the spaces around it
+    let women = Filter {
+        category: Some(" Women ".to_string()),
+        ..Filter::default()
+    };
+    let calendar = ical::calendar("Innsbruck", &[innsbruck()], &women);
+    assert!(calendar.contains("UID:event-1297@boulderboard"));
+    assert!(!calendar.contains("UID:round-7685@boulderboard"));
+
+    let blank = Filter {
+        category: Some(" ".to_string()),
+        ..Filter::default()
+    };
+    assert!(blank.is_empty());
+    let calendar = ical::calendar("Innsbruck", &[innsbruck()], &blank);
+    assert!(calendar.contains("UID:round-7685@boulderboard"));
+}
+
+#[test]
+fn categories_without_details() {
+    // Older events don't always have the discipline and name of a category
+    let mut entry = innsbruck();
+    let men = &mut entry.details.as_mut().unwrap().dcats[0];
+    men.discipline_kind.clear();
+    men.category_name.clear();
+    let women = Filter {
+        discipline: Some("boulder".to_string()),
+        league: None,
+        category: Some("women".to_string()),
+    };
+    let calendar = ical::calendar("Innsbruck", &[entry], &women);
+    assert!(calendar.contains("UID:round-7685@boulderboard"));
+}
+
+#[tokio::test]
+async fn season_from_the_api() {
+    mock::start();
+    let (name, entries) = ical::fetch_season(35).await.unwrap();
+    let calendar = ical::calendar(&name, &entries, &Filter::default());
+    assert_eq!(calendar.matches("BEGIN:VEVENT").count(), entries.len());
+    assert!(calendar.contains("X-WR-CALNAME:2023"));
+
+    // The disciplines and leagues are listed in the season
+    let boulder = Filter {
+        discipline: Some("boulder".to_string()),
+        ..Filter::default()
+    };
+    let calendar = ical::calendar(&name, &entries, &boulder);
+    assert_eq!(calendar.matches("BEGIN:VEVENT").count(), 3);
+    let youth = Filter {
+        league: Some("IFSC Youth".to_string()),
+        ..Filter::default()
+    };
+    let calendar = ical::calendar(&name, &entries, &youth);
+    assert_eq!(calendar.matches("BEGIN:VEVENT").count(), 1);
+
+    // The categories are only known for event 1297, which is served by the
+    // mock
+    let women = Filter {
+        category: Some("Women".to_string()),
+        ..Filter::default()
+    };
+    let calendar = ical::calendar(&name, &entries, &women);
+    assert!(calendar.contains("UID:event-1297@boulderboard"));
+    assert_eq!(calendar.matches("BEGIN:VEVENT").count(), 1);
+}
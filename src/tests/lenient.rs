@@ -0,0 +1,127 @@
+//! Results that are incomplete or in a format we don't know should still be
+//! shown as far as possible.
+use crate::api::result::{DisciplineTag, Results, Status};
+use crate::discipline::{Boulder, Lead, Score, Unknown};
+use crate::{export, ranking};
+
+fn results(discipline: &str, ascents: &str) -> Results {
+    let json = format!(
+        r#"{{
+            "discipline": "{discipline}",
+            "event": null,
+            "category": "Women",
+            "round": "Final",
+            "ranking": [
+                {{
+                    "athlete_id": 1,
+                    "firstname": "",
+                    "lastname": "DOE",
+                    "country": "AUT",
+                    "flag_url": "",
+                    "rank": 1,
+                    "ascents": {ascents},
+                    "active": false
+                }}
+            ]
+        }}"#
+    );
+    serde_json::from_str(&json).unwrap()
+}
+
+#[test]
+fn unknown_discipline() {
+    let results = results("Combined", r#"[{"status": "confirmed", "points": 25}]"#);
+    assert_eq!(results.discipline, DisciplineTag::Unknown);
+
+    let athletes = ranking::rank::<Unknown>(&results);
+    assert_eq!(athletes[0].rank, 1);
+    assert!(athletes[0].problems.is_empty());
+    assert!(ranking::disagreements(&results).is_empty());
+}
+
+#[test]
+fn lead_ascent_without_score() {
+    let results = results(
+        "Lead",
+        r#"[{"status": "confirmed", "score": "TOP"}, {"status": "active"}]"#,
+    );
+    let athletes = ranking::rank::<Lead>(&results);
+    assert!(athletes[0].ascents[1].is_none());
+    assert_eq!(
+        athletes[0].problems,
+        ["Route 2: missing or invalid `score`"]
+    );
+}
+
+#[test]
+fn boulder_ascent_with_points() {
+    // The points format has no zone, and we don't want a single odd field to
+    // make the whole results unreadable.
+    let results = results(
+        "Boulder",
+        r#"[
+            {"status": "confirmed", "top": true, "top_tries": 2, "zone": true, "zone_tries": 1},
+            {"status": "confirmed", "top": "yes", "points": 25, "top_tries": 2}
+        ]"#,
+    );
+    let athletes = ranking::rank::<Boulder>(&results);
+    assert!(athletes[0].ascents[1].is_none());
+    assert_eq!(athletes[0].problems, ["Route 2: missing or invalid `top`"]);
+}
+
+#[test]
+fn unreadable_middle_route() {
+    // The routes after the unreadable one keep their place
+    let results = results(
+        "Boulder",
+        r#"[
+            {"status": "confirmed", "top": true, "top_tries": 1, "zone": true, "zone_tries": 1},
+            {"status": "confirmed", "zone": true, "zone_tries": 1},
+            {"status": "confirmed", "top": false, "zone": true, "zone_tries": 3}
+        ]"#,
+    );
+    let athletes = ranking::rank::<Boulder>(&results);
+    let ascents: Vec<_> = athletes[0]
+        .ascents
+        .iter()
+        .map(|a| a.as_ref().map(|a| a.to_string()))
+        .collect();
+    assert_eq!(ascents, [Some("T1 Z1".into()), None, Some("Z3".into())]);
+    assert_eq!(athletes[0].score.official(), "1T2z 1 4");
+
+    let table = export::results_table(&results);
+    assert_eq!(table.rows[0][5..8], ["T1 Z1", "", "Z3"]);
+}
+
+#[test]
+fn unknown_status() {
+    let results = results(
+        "Boulder",
+        r#"[{"status": "under_review", "top": true, "top_tries": 1, "zone": true, "zone_tries": 1}]"#,
+    );
+    assert_eq!(results.ranking[0].ascents[0].status, Status::Unknown);
+    let athletes = ranking::rank::<Boulder>(&results);
+    assert!(athletes[0].ascents[0].is_some());
+}
+
+#[test]
+fn unreadable_active() {
+    let json = r#"{
+        "discipline": "Lead",
+        "event": null,
+        "category": "Women",
+        "round": "Final",
+        "ranking": [{
+            "athlete_id": 1,
+            "firstname": "",
+            "lastname": "DOE",
+            "country": "AUT",
+            "flag_url": "",
+            "rank": 1,
+            "ascents": [],
+            "active": "yes"
+        }]
+    }"#;
+    let results: Results = serde_json::from_str(json).unwrap();
+    assert!(!results.ranking[0].active);
+}
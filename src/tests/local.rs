@@ -0,0 +1,122 @@
+//! Scorecards of the judges of local competitions
+use crate::api::result::Status;
+use crate::local::{Competition, CorrectionError, JudgeAction};
+
+fn competition() -> Competition {
+    let mut competition = Competition::new(0, "Club".into(), "Women".into(), "Final".into());
+    competition.add_athlete("Ann".into(), "A".into(), "AUT".into());
+    competition.add_athlete("Bo".into(), "B".into(), "NED".into());
+    competition.add_route("1".into());
+    competition
+}
+
+#[test]
+fn apply_actions() {
+    let mut c = competition();
+    let card = c.scorecard_mut(0, 0);
+
+    // Nothing can be scored before the athlete is on the wall
+    assert!(!card.apply(JudgeAction::Attempt));
+    assert!(!card.apply(JudgeAction::Confirm));
+
+    c.start(0, 0);
+    let card = c.scorecard_mut(0, 0);
+    assert_eq!(card.status, Status::Active);
+    assert!(card.apply(JudgeAction::Attempt));
+    assert!(card.apply(JudgeAction::Attempt));
+    assert!(card.apply(JudgeAction::Top));
+    // A top implies the zone, and can't be scored twice
+    assert_eq!((card.attempts, card.zone, card.top), (2, Some(2), Some(2)));
+    assert!(!card.apply(JudgeAction::Zone));
+    assert!(!card.apply(JudgeAction::Top));
+
+    assert!(!card.apply(JudgeAction::Confirm));
+    assert!(card.apply(JudgeAction::Finish));
+    assert!(card.apply(JudgeAction::Lock));
+    assert!(card.apply(JudgeAction::Confirm));
+    assert_eq!(card.status, Status::Confirmed);
+    assert!(!card.apply(JudgeAction::Attempt));
+}
+
+#[test]
+fn undo_actions() {
+    let mut c = competition();
+    c.start(0, 0);
+    let card = c.scorecard_mut(0, 0);
+    card.apply(JudgeAction::Attempt);
+    card.apply(JudgeAction::Zone);
+    card.undo();
+    assert_eq!((card.attempts, card.zone), (1, None));
+
+    card.undo();
+    card.undo();
+    assert_eq!(card.status, Status::Pending);
+    assert!(!card.can_undo());
+
+    // Locking can't be undone, so the result stays locked
+    c.start(0, 0);
+    let card = c.scorecard_mut(0, 0);
+    card.apply(JudgeAction::Finish);
+    card.apply(JudgeAction::Lock);
+    assert!(!card.can_undo());
+    card.undo();
+    assert_eq!(card.status, Status::Locked);
+}
+
+#[test]
+fn undo_is_limited() {
+    let mut c = competition();
+    c.start(0, 0);
+    let card = c.scorecard_mut(0, 0);
+    for _ in 0..100 {
+        card.apply(JudgeAction::Attempt);
+    }
+    while card.can_undo() {
+        card.undo();
+    }
+    assert_eq!(card.attempts, 80);
+}
+
+#[test]
+fn start_finishes_the_previous_athlete() {
+    let mut c = competition();
+    c.start(0, 0);
+    assert_eq!(c.active_athlete(0), Some(0));
+
+    c.start(1, 0);
+    assert_eq!(c.active_athlete(0), Some(1));
+    assert_eq!(c.scorecard(0, 0).unwrap().status, Status::Pending);
+
+    // Starting an athlete that is already done changes nothing
+    let card = c.scorecard_mut(1, 0);
+    card.apply(JudgeAction::Finish);
+    card.apply(JudgeAction::Lock);
+    c.start(1, 0);
+    assert_eq!(c.scorecard(1, 0).unwrap().status, Status::Locked);
+    assert_eq!(c.active_athlete(0), None);
+}
+
+#[test]
+fn admin_corrections() {
+    let mut c = competition();
+    let card = c.scorecard_mut(0, 0);
+    assert_eq!(
+        card.correct(Some(3), Some(2)),
+        Err(CorrectionError::ZoneAfterTop)
+    );
+    assert_eq!(card.correct(Some(0), None), Err(CorrectionError::NoAttempt));
+
+    // A top implies the zone, and the corrections can be undone
+    assert_eq!(card.correct(None, Some(4)), Ok(()));
+    assert_eq!((card.attempts, card.zone, card.top), (4, Some(4), Some(4)));
+    assert_eq!(card.correct(Some(2), Some(4)), Ok(()));
+    card.undo();
+    assert_eq!(card.zone, Some(4));
+    assert_eq!(card.status, Status::Pending);
+
+    card.apply(JudgeAction::Lock);
+    assert_eq!(card.correct(None, None), Err(CorrectionError::Locked));
+    c.start(1, 0);
+    let card = c.scorecard_mut(1, 0);
+    assert_eq!(card.correct(Some(1), None), Err(CorrectionError::Active));
+}
@@ -0,0 +1,59 @@
+//! Tests that run the whole pipeline, from the API to the rankings
+use crate::api::result::{Athlete, Country, DisciplineTag, RankAthlete, Results};
+
+mod api;
+mod calendar;
+mod golden;
+mod head_to_head;
+#[cfg(feature = "desktop")]
+mod history;
+mod ical;
+mod lenient;
+mod local;
+mod notifications;
+mod paraclimbing;
+mod predictions;
+mod rating;
+mod search;
+#[cfg(feature = "server")]
+mod server;
+mod speed;
+mod standings;
+
+/// An athlete from Austria with the given id
+fn athlete(id: u64) -> Athlete {
+    Athlete {
+        athlete_id: id,
+        firstname: String::new(),
+        lastname: format!("ATHLETE {id}"),
+        country: Country {
+            country: "AUT".into(),
+            flag_url: String::new(),
+        },
+    }
+}
+
+/// A round with the given official ranks, by athlete id
+fn round(discipline: DisciplineTag, ranks: &[(u64, Option<u64>)]) -> Results {
+    Results {
+        discipline,
+        event: Some("Event".into()),
+        category: "Women".into(),
+        round: "Final".into(),
+        ranking: ranks
+            .iter()
+            .map(|&(id, rank)| RankAthlete {
+                athlete: athlete(id),
+                rank,
+                score: None,
+                starting_group: None,
+                group_rank: None,
+                paraclimbing_sport_class: None,
+                sport_class_state: None,
+                route_start_orders: Vec::new(),
+                ascents: Vec::new(),
+                active: false,
+            })
+            .collect(),
+    }
+}
@@ -0,0 +1,73 @@
+//! Notifications found by comparing successive results of a round
+use crate::api::result::{Results, Status};
+use crate::favourites::{FavouriteAthlete, Favourites};
+use crate::notifications::{self, Notification, Rules};
+
+fn fixture(name: &str) -> Results {
+    let path = format!("{}/test_data/{name}", env!("CARGO_MANIFEST_DIR"));
+    serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+}
+
+fn following(athlete_id: u64) -> Favourites {
+    Favourites {
+        athletes: vec![FavouriteAthlete {
+            athlete_id,
+            firstname: String::new(),
+            lastname: String::new(),
+            country: String::new(),
+        }],
+        countries: Vec::new(),
+    }
+}
+
+#[test]
+fn favourite_tops_a_boulder() {
+    let previous = fixture("boulder_live.json");
+    let mut current = previous.clone();
+    // The active athlete tops the boulder they are on
+    let athlete = &mut current.ranking[0];
+    athlete.ascents[3].boulder.top = Some(true);
+    athlete.ascents[3].boulder.top_tries = Some(2);
+    let id = athlete.athlete.athlete_id;
+    let name = format!("{} {}", athlete.athlete.firstname, athlete.athlete.lastname);
+
+    let changes = notifications::diff(&previous, &current, &following(id), &Rules::default());
+    assert_eq!(
+        changes,
+        [Notification::Topped {
+            athlete: name,
+            route: 4
+        }]
+    );
+
+    // Nothing happens for athletes that are not followed
+    let changes = notifications::diff(&previous, &current, &following(0), &Rules::default());
+    assert!(changes.is_empty());
+
+    let rules = Rules {
+        topped: false,
+        ..Rules::default()
+    };
+    let changes = notifications::diff(&previous, &current, &following(id), &rules);
+    assert!(changes.is_empty());
+}
+
+#[test]
+fn round_starts_and_finishes() {
+    let live = fixture("boulder_live_3.json");
+    let mut pending = live.clone();
+    for athlete in &mut pending.ranking {
+        athlete.active = false;
+        for ascent in &mut athlete.ascents {
+            ascent.status = Status::Pending;
+        }
+    }
+
+    let rules = Rules::default();
+    let favourites = Favourites::default();
+    assert_eq!(
+        notifications::diff(&pending, &live, &favourites, &rules),
+        [Notification::RoundStarted, Notification::RoundFinished]
+    );
+    assert!(notifications::diff(&live, &live, &favourites, &rules).is_empty());
+}
@@ -0,0 +1,54 @@
+//! Paraclimbing rounds, with sport classes and start orders per route
+use crate::api::result::{Results, RouteStartOrder};
+use crate::discipline::{Lead, Score};
+use crate::ranking;
+
+fn results() -> Results {
+    let path = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/test_data/results_lead_live.json"
+    );
+    serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+}
+
+#[test]
+fn sport_classes() {
+    let results = results();
+    let athlete = &results.ranking[0];
+    assert_eq!(
+        athlete.paraclimbing_sport_class.as_deref(),
+        Some("Visually Impaired Climbing: B1")
+    );
+    assert_eq!(athlete.sport_class(), Some("B1"));
+    let states: Vec<_> = results
+        .ranking
+        .iter()
+        .map(|a| a.sport_class_state.as_deref().unwrap())
+        .collect();
+    assert_eq!(
+        states,
+        [
+            "fixed_review_date",
+            "confirmed",
+            "fixed_review_date",
+            "fixed_review_date"
+        ]
+    );
+
+    // The routes are sorted by name, not by the order of the fields
+    let order = |route_name: &str, position| RouteStartOrder {
+        route_name: route_name.into(),
+        position,
+    };
+    assert_eq!(athlete.route_start_orders, [order("C", 1), order("D", 3)]);
+}
+
+#[test]
+fn scores_from_route_ranks() {
+    let results = results();
+    let athletes = ranking::rank::<Lead>(&results);
+    let scores: Vec<_> = athletes.iter().map(|a| a.score.official()).collect();
+    assert_eq!(scores, ["1.58", "2.24", "2.74", "3.16"]);
+    assert_eq!(athletes[0].sport_class.as_deref(), Some("B1"));
+    assert!(ranking::disagreements(&results).is_empty());
+}
@@ -0,0 +1,158 @@
+//! Submitting predictions for rounds and scoring them afterwards
+use crate::api::{mock, seasons::CategoryRound};
+use crate::predictions::{
+    self, PredictedAthlete, Prediction, Predictions, Score, Standing, SubmitError,
+};
+use chrono::{Duration, Utc};
+
+fn prediction(predictor: &str, round_id: u64, athletes: &[u64]) -> Prediction {
+    Prediction {
+        predictor: predictor.into(),
+        event_id: 1297,
+        round_id,
+        round: String::new(),
+        athletes: athletes
+            .iter()
+            .map(|&id| PredictedAthlete {
+                athlete_id: id,
+                firstname: String::new(),
+                lastname: format!("ATHLETE {id}"),
+            })
+            .collect(),
+        submitted_at: Utc::now(),
+    }
+}
+
+#[test]
+fn submit_before_the_round() {
+    let now = Utc::now();
+    let ends_at = now + Duration::days(2);
+    let mut round = CategoryRound {
+        category_round_id: 1,
+        name: "Final".into(),
+        starts_at: Some(now + Duration::hours(1)),
+        ends_at: None,
+        status: String::new(),
+    };
+    let mut all = Predictions::default();
+
+    let errors = [
+        prediction(" ", 1, &[1, 2, 3]),
+        prediction("Ann", 1, &[]),
+        prediction("Ann", 1, &[1, 2, 1]),
+    ]
+    .map(|p| all.submit(p, &round, ends_at, now));
+    assert_eq!(
+        errors,
+        [
+            Err(SubmitError::NoPredictor),
+            Err(SubmitError::NoAthletes),
+            Err(SubmitError::SameAthleteTwice),
+        ]
+    );
+
+    // A new prediction replaces the old one of the same predictor
+    all.submit(prediction("Ann", 1, &[1, 2, 3]), &round, ends_at, now)
+        .unwrap();
+    all.submit(prediction("Bob", 1, &[3, 2, 1]), &round, ends_at, now)
+        .unwrap();
+    all.submit(prediction("Ann", 1, &[2, 1, 3]), &round, ends_at, now)
+        .unwrap();
+    let predictions: Vec<_> = all
+        .predictions
+        .iter()
+        .map(|p| (p.predictor.as_str(), p.athletes[0].athlete_id))
+        .collect();
+    assert_eq!(predictions, [("Bob", 3), ("Ann", 2)]);
+    assert_eq!(all.rounds(), [(1297, 1)]);
+
+    round.status = "active".into();
+    assert_eq!(
+        all.submit(prediction("Cy", 1, &[1]), &round, ends_at, now),
+        Err(SubmitError::Started)
+    );
+
+    // An outdated status doesn't allow predictions after the start
+    round.status = "pending".into();
+    round.starts_at = Some(now - Duration::minutes(1));
+    assert_eq!(
+        all.submit(prediction("Cy", 1, &[1]), &round, ends_at, now),
+        Err(SubmitError::Started)
+    );
+
+    // A round of an event that is over can't be predicted, even without a
+    // status or schedule
+    round.status = String::new();
+    round.starts_at = None;
+    let ended = now - Duration::days(365);
+    assert_eq!(
+        all.submit(prediction("Cy", 1, &[1]), &round, ended, now),
+        Err(SubmitError::Started)
+    );
+}
+
+#[test]
+fn scores() {
+    let ranks = [(10, 2), (11, 1), (12, 3), (13, 4)];
+    let score = |athletes: &[u64]| predictions::score(&prediction("Ann", 1, athletes), &ranks);
+    assert_eq!(
+        score(&[11, 10, 12]),
+        Score {
+            points: 9,
+            exact: 3
+        }
+    );
+    assert_eq!(
+        score(&[10, 11, 13]),
+        Score {
+            points: 2,
+            exact: 0
+        }
+    );
+    // Athletes that didn't climb score nothing
+    assert_eq!(
+        score(&[11, 99, 14]),
+        Score {
+            points: 3,
+            exact: 1
+        }
+    );
+}
+
+#[tokio::test]
+async fn predictors_leaderboard() {
+    mock::start();
+
+    // Grossman and Nonaka share the win of the qualification, Raboutou and Seo
+    // are third
+    let (grossman, nonaka, raboutou, seo) = (1803, 2294, 1811, 2080);
+    let all = Predictions {
+        predictions: vec![
+            prediction("Ann", 7686, &[nonaka, grossman, raboutou]),
+            prediction("Bob", 7686, &[grossman, raboutou, nonaka]),
+            prediction("Cy", 7686, &[grossman, 3840, 12297]),
+            // The final is not served by the mock, so it can't be scored
+            prediction("Ann", 8252, &[grossman, nonaka, seo]),
+        ],
+    };
+
+    let finished = predictions::fetch_finished(&all).await;
+    let rounds: Vec<_> = finished.iter().map(|(id, _)| *id).collect();
+    assert_eq!(rounds, [7686]);
+
+    let standing = |rank, predictor: &str, points, exact| Standing {
+        rank,
+        predictor: predictor.into(),
+        rounds: 1,
+        points,
+        exact,
+    };
+    assert_eq!(
+        predictions::leaderboard(&all.predictions, &finished),
+        [
+            standing(1, "Ann", 7, 2),
+            standing(2, "Bob", 5, 1),
+            standing(3, "Cy", 3, 1),
+        ]
+    );
+}
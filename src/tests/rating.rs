@@ -0,0 +1,95 @@
+//! Elo ratings from the ranks of the rounds
+use super::{athlete, round};
+use crate::api::result::DisciplineTag;
+use crate::rating::{self, Ratings, INITIAL};
+use chrono::{TimeZone, Utc};
+
+#[test]
+fn expected_score() {
+    assert_eq!(rating::expected(1500.0, 1500.0), 0.5);
+    assert!(rating::expected(1700.0, 1500.0) > 0.75);
+    let sum = rating::expected(1620.0, 1480.0) + rating::expected(1480.0, 1620.0);
+    assert!((sum - 1.0).abs() < 1e-9);
+}
+
+#[test]
+fn ratings_after_rounds() {
+    let at = Utc.with_ymd_and_hms(2023, 6, 14, 0, 0, 0).unwrap();
+    let mut ratings = Ratings::default();
+    ratings.add_round(
+        at,
+        &round(
+            DisciplineTag::Boulder,
+            &[(1, Some(1)), (2, Some(2)), (3, Some(3)), (4, None)],
+        ),
+    );
+
+    // The middle athlete won and lost as often as expected
+    assert!(ratings.rating(&DisciplineTag::Boulder, 1) > INITIAL);
+    assert_eq!(ratings.rating(&DisciplineTag::Boulder, 2), INITIAL);
+    assert!(ratings.rating(&DisciplineTag::Boulder, 3) < INITIAL);
+    let total: f64 = (1..=3)
+        .map(|id| ratings.rating(&DisciplineTag::Boulder, id))
+        .sum();
+    assert!((total - 3.0 * INITIAL).abs() < 1e-9);
+
+    // Athletes without a rank did not climb, other disciplines are separate
+    assert!(ratings.track(&DisciplineTag::Boulder, 4).is_none());
+    assert_eq!(ratings.rating(&DisciplineTag::Lead, 1), INITIAL);
+
+    // A draw between equal athletes changes nothing
+    ratings.add_round(
+        at,
+        &round(DisciplineTag::Lead, &[(1, Some(1)), (2, Some(1))]),
+    );
+    assert_eq!(ratings.rating(&DisciplineTag::Lead, 1), INITIAL);
+
+    let track = ratings.track(&DisciplineTag::Boulder, 1).unwrap();
+    assert_eq!(track.points.len(), 1);
+    assert_eq!(track.trend(5), track.current() - INITIAL);
+    assert_eq!(ratings.position(&DisciplineTag::Boulder, 3), Some(3));
+    assert_eq!(ratings.athlete(1).len(), 2);
+}
+
+#[test]
+fn predicted_ranking() {
+    let at = Utc.with_ymd_and_hms(2023, 6, 14, 0, 0, 0).unwrap();
+    let mut ratings = Ratings::default();
+    ratings.add_round(
+        at,
+        &round(DisciplineTag::Boulder, &[(1, Some(1)), (2, Some(2))]),
+    );
+
+    // Unrated athletes get the initial rating and share their rank
+    let predictions: Vec<_> = ratings
+        .predict(
+            &DisciplineTag::Boulder,
+            &[athlete(2), athlete(5), athlete(1), athlete(3)],
+        )
+        .into_iter()
+        .map(|p| (p.rank, p.athlete.athlete_id))
+        .collect();
+    assert_eq!(predictions, [(1, 1), (2, 3), (2, 5), (4, 2)]);
+}
+
+#[cfg(feature = "desktop")]
+#[tokio::test]
+async fn ratings_from_history() {
+    use crate::api::mock;
+    use crate::history::History;
+
+    mock::start();
+    let history = History::open_in_memory().unwrap();
+    history.sync_season(35).await.unwrap().unwrap();
+    let ratings = history.ratings().unwrap();
+
+    // Nonaka shared the win of the qualification with Grossman and was third
+    // in the semi-final, which Raboutou won
+    let top: Vec<_> = ratings
+        .top(&DisciplineTag::Boulder, 2)
+        .into_iter()
+        .map(|t| (t.athlete.athlete_id, t.points.len()))
+        .collect();
+    assert_eq!(top, [(2294, 2), (1811, 2)]);
+    assert!(ratings.rating(&DisciplineTag::Boulder, 1803) > INITIAL);
+}
@@ -0,0 +1,51 @@
+//! Fuzzy matching of search queries
+use crate::api::result::DisciplineTag;
+use crate::search;
+
+#[test]
+fn fuzzy_matching() {
+    let innsbruck = "IFSC World Cup Innsbruck 2023";
+    assert!(search::score("innsbruck", innsbruck).is_some());
+    assert!(search::score("insbruk", innsbruck).is_some());
+    assert!(search::score("cup 2023", innsbruck).is_some());
+    assert!(search::score("salt lake", innsbruck).is_none());
+}
+
+#[test]
+fn better_matches_score_higher() {
+    let exact = search::score("brixen", "European Youth Cup Brixen").unwrap();
+    let scattered = search::score("brixen", "Bern Riga Xian Enschede").unwrap_or(0);
+    assert!(exact > scattered);
+
+    // Matching the start of a word beats matching somewhere in the middle
+    let start = search::score("lead", "Lead Women").unwrap();
+    let middle = search::score("lead", "Paraclimbing Pleaded").unwrap();
+    assert!(start > middle);
+}
+
+#[test]
+fn remember_seen_athletes() {
+    let round = super::round(DisciplineTag::Boulder, &[(1, Some(1)), (2, Some(2))]);
+    let mut seen = Vec::new();
+    assert!(search::remember(&mut seen, "Innsbruck", 7686, &round));
+    // The same round again doesn't change the store, so it isn't written
+    assert!(!search::remember(&mut seen, "Innsbruck", 7686, &round));
+
+    // An athlete seen again moves to the end, the oldest are forgotten
+    let next = super::round(DisciplineTag::Boulder, &[(1, Some(1))]);
+    assert!(search::remember(&mut seen, "Bern", 9000, &next));
+    let ids: Vec<_> = seen.iter().map(|s| (s.athlete_id, s.round_id)).collect();
+    assert_eq!(ids, [(2, 7686), (1, 9000)]);
+
+    let many: Vec<_> = (0..search::SEEN_LIMIT as u64 + 10)
+        .map(|id| (id + 100, None))
+        .collect();
+    search::remember(
+        &mut seen,
+        "Bern",
+        9000,
+        &super::round(DisciplineTag::Boulder, &many),
+    );
+    assert_eq!(seen.len(), search::SEEN_LIMIT);
+    assert_eq!(seen[0].athlete_id, 110);
+}
@@ -0,0 +1,41 @@
+//! The HTTP server with our rankings
+use crate::api::mock;
+use crate::server::{self, Rounds};
+use std::net::SocketAddr;
+use std::time::Duration;
+
+/// Serve the rankings on a free port
+fn start(rounds: Rounds) -> SocketAddr {
+    let server = axum::Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0)))
+        .serve(server::app(rounds).into_make_service());
+    let addr = server.local_addr();
+    tokio::spawn(server);
+    addr
+}
+
+#[tokio::test]
+async fn rounds() {
+    mock::start();
+    let rounds = Rounds::default();
+    let addr = start(rounds.clone());
+
+    let response = reqwest::get(format!("http://{addr}/rounds/7686"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let ranking: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(ranking["discipline"], "Boulder");
+    assert_eq!(ranking["athletes"][0]["rank"], 1);
+    assert!(rounds.is_polling(7686));
+
+    // Nobody is subscribed anymore, so the round isn't polled anymore
+    tokio::time::sleep(Duration::from_millis(1500)).await;
+    assert!(!rounds.is_polling(7686));
+
+    // Rounds that can't be loaded aren't polled at all
+    let response = reqwest::get(format!("http://{addr}/rounds/1"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 502);
+    assert!(!rounds.is_polling(1));
+}
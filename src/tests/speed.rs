@@ -0,0 +1,55 @@
+//! Speed rounds, where the fastest time wins
+use crate::api::result::Results;
+use crate::discipline::{Score, Speed};
+use crate::ranking;
+
+/// A round with the times of the athletes by athlete id, `None` for athletes
+/// without a valid time
+fn results(times: &[(u64, Option<u64>)]) -> Results {
+    let ranking: Vec<_> = times
+        .iter()
+        .enumerate()
+        .map(|(i, (id, time_ms))| {
+            serde_json::json!({
+                "athlete_id": id,
+                "firstname": "",
+                "lastname": format!("ATHLETE {id}"),
+                "country": "AUT",
+                "flag_url": "",
+                "rank": i + 1,
+                "ascents": [{"status": "confirmed", "time_ms": time_ms}],
+                "active": false,
+            })
+        })
+        .collect();
+    serde_json::from_value(serde_json::json!({
+        "discipline": "Speed",
+        "event": null,
+        "category": "Women",
+        "round": "Final",
+        "ranking": ranking,
+    }))
+    .unwrap()
+}
+
+#[test]
+fn fastest_first() {
+    let results = results(&[(1, Some(6500)), (2, None), (3, Some(5900))]);
+    let mut athletes = ranking::rank::<Speed>(&results);
+    athletes.sort_by_key(|a| a.rank);
+    let ranked: Vec<_> = athletes
+        .iter()
+        .map(|a| (a.id, a.score.official()))
+        .collect();
+    assert_eq!(
+        ranked,
+        [
+            (3, "5.900".to_string()),
+            (1, "6.500".to_string()),
+            (2, String::new())
+        ]
+    );
+
+    // The official ranks of knockout heats aren't based on the times
+    assert!(ranking::disagreements(&results).is_empty());
+}
@@ -0,0 +1,204 @@
+//! The World Cup ranking over the events of a season
+use super::{athlete, round};
+use crate::api::{mock, result::DisciplineTag};
+use crate::standings::{self, EventResult, Placing};
+
+/// An event with the given places, by athlete id
+fn event(id: u64, places: &[(u64, u64)]) -> EventResult {
+    EventResult {
+        event_id: id,
+        event: format!("Event {id}"),
+        placings: places
+            .iter()
+            .map(|&(athlete_id, rank)| Placing {
+                athlete: athlete(athlete_id),
+                rank,
+            })
+            .collect(),
+    }
+}
+
+#[test]
+fn points_table() {
+    assert_eq!(standings::points(1, 1), 1000);
+    assert_eq!(standings::points(30, 1), 40);
+    assert_eq!(standings::points(31, 1), 0);
+    // Two athletes tied for second share the points of second and third
+    assert_eq!(standings::points(2, 2), (805 + 690) / 2);
+    assert_eq!(standings::points(30, 2), 20);
+}
+
+#[test]
+fn event_ranking_over_rounds() {
+    let qualification = round(
+        DisciplineTag::Boulder,
+        &[
+            (1, Some(3)),
+            (2, Some(1)),
+            (3, Some(2)),
+            (4, Some(4)),
+            (5, None),
+        ],
+    );
+    let semi_final = round(
+        DisciplineTag::Boulder,
+        &[(1, Some(1)), (2, Some(2)), (3, Some(2))],
+    );
+    let final_ = round(DisciplineTag::Boulder, &[(1, Some(2)), (2, Some(1))]);
+
+    let placings = standings::event_ranking(&[qualification, semi_final, final_]);
+    let ranks: Vec<_> = placings
+        .iter()
+        .map(|p| (p.athlete.athlete_id, p.rank))
+        .collect();
+    // Athlete 5 did not start
+    assert_eq!(ranks, [(2, 1), (1, 2), (3, 3), (4, 4)]);
+}
+
+#[test]
+fn ranks_in_every_round() {
+    let qualification = round(
+        DisciplineTag::Boulder,
+        &[(1, Some(2)), (2, Some(1)), (3, Some(3))],
+    );
+    let final_ = round(DisciplineTag::Boulder, &[(1, Some(1)), (2, Some(2))]);
+
+    let results = standings::full_results(&[qualification, final_]);
+    let rows: Vec<_> = results
+        .iter()
+        .map(|r| {
+            (
+                r.placing.athlete.athlete_id,
+                r.placing.rank,
+                r.rounds.clone(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        rows,
+        [
+            (1, 1, vec![Some(2), Some(1)]),
+            (2, 2, vec![Some(1), Some(2)]),
+            (3, 3, vec![Some(3), None]),
+        ]
+    );
+}
+
+#[test]
+fn ties_in_the_semi_final_are_broken_by_countback() {
+    let qualification = round(DisciplineTag::Boulder, &[(1, Some(2)), (2, Some(1))]);
+    let semi_final = round(DisciplineTag::Boulder, &[(1, Some(1)), (2, Some(1))]);
+
+    let placings = standings::event_ranking(&[qualification, semi_final]);
+    let ranks: Vec<_> = placings
+        .iter()
+        .map(|p| (p.athlete.athlete_id, p.rank))
+        .collect();
+    assert_eq!(ranks, [(2, 1), (1, 2)]);
+}
+
+#[test]
+fn best_results_count() {
+    let events = [
+        event(1, &[(1, 1), (2, 2), (3, 2)]),
+        event(2, &[(2, 1), (1, 4)]),
+        event(3, &[(1, 30), (3, 1)]),
+    ];
+
+    let all = standings::standings(&events, None);
+    let totals: Vec<_> = all
+        .iter()
+        .map(|s| (s.athlete.athlete_id, s.rank, s.points))
+        .collect();
+    assert_eq!(totals, [(2, 1, 1747), (3, 1, 1747), (1, 3, 1650)]);
+
+    let best = standings::standings(&events, Some(1));
+    let athlete_1 = best.iter().find(|s| s.athlete.athlete_id == 1).unwrap();
+    assert_eq!(athlete_1.points, 1000);
+    let counted: Vec<_> = athlete_1
+        .events
+        .iter()
+        .map(|e| e.as_ref().map(|e| e.counted))
+        .collect();
+    assert_eq!(counted, [Some(true), Some(false), Some(false)]);
+}
+
+#[tokio::test]
+async fn season_from_the_api() {
+    mock::start();
+    let categories = standings::fetch_season(35).await.unwrap();
+    // The paraclimbing event 1337 is served by the mock as well, but it
+    // doesn't count for the World Cup
+    let names: Vec<_> = categories.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, ["BOULDER Men", "BOULDER Women"]);
+
+    // Only World Cup 1297 is served by the mock
+    let women = &categories[1];
+    assert_eq!(women.events.len(), 1);
+    assert_eq!(women.events[0].event_id, 1297);
+    let ranking = standings::standings(&women.events, None);
+    assert_eq!(ranking[0].rank, 1);
+    assert_eq!(ranking[0].points, 1000);
+}
+
+/// The final ranking of a category, with the country of every athlete
+fn category(places: &[(u64, &str, u64)]) -> EventResult {
+    EventResult {
+        event_id: 1,
+        event: "Event 1".into(),
+        placings: places
+            .iter()
+            .map(|&(id, country, rank)| {
+                let mut athlete = athlete(id);
+                athlete.country.country = country.into();
+                Placing { athlete, rank }
+            })
+            .collect(),
+    }
+}
+
+#[test]
+fn medals() {
+    let men = category(&[(1, "JPN", 1), (2, "AUT", 2), (3, "JPN", 3), (4, "SLO", 4)]);
+    // A tie for bronze gives two bronze medals
+    let women = category(&[(5, "SLO", 1), (6, "AUT", 2), (7, "JPN", 3), (8, "USA", 3)]);
+
+    let table: Vec<_> = standings::medal_table([&men, &women])
+        .into_iter()
+        .map(|m| (m.country, m.rank, m.gold, m.silver, m.bronze))
+        .collect();
+    assert_eq!(
+        table,
+        [
+            ("JPN".to_string(), 1, 1, 0, 2),
+            ("SLO".to_string(), 2, 1, 0, 0),
+            ("AUT".to_string(), 3, 0, 2, 0),
+            ("USA".to_string(), 4, 0, 0, 1),
+        ]
+    );
+}
+
+#[test]
+fn national_teams() {
+    // Only the best three athletes of a country count
+    let men = category(&[
+        (1, "JPN", 1),
+        (2, "JPN", 2),
+        (3, "JPN", 3),
+        (4, "JPN", 4),
+        (5, "AUT", 5),
+    ]);
+    let women = category(&[(6, "AUT", 1)]);
+
+    let ranking: Vec<_> = standings::team_ranking([&men, &women])
+        .into_iter()
+        .map(|t| (t.country, t.rank, t.points))
+        .collect();
+    assert_eq!(
+        ranking,
+        [
+            ("JPN".to_string(), 1, 1000 + 805 + 690),
+            ("AUT".to_string(), 2, 545 + 1000),
+        ]
+    );
+}